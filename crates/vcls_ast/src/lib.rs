@@ -1,13 +1,78 @@
-use std::str::FromStr;
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
 
 #[derive(Debug, PartialEq, Clone, Eq, Hash, Copy)]
 pub struct Span(pub usize, pub usize);
 
+/// A name with its location, e.g. of a declaration, a label or an object property.
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+pub struct Ident {
+    pub span: Span,
+    pub name: String,
+}
+
+impl Ident {
+    pub fn new(name: impl Into<String>, span: Span) -> Self {
+        Self {
+            span,
+            name: name.into(),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.name
+    }
+}
+
+impl PartialEq<str> for Ident {
+    fn eq(&self, other: &str) -> bool {
+        self.name == other
+    }
+}
+
+impl PartialEq<&str> for Ident {
+    fn eq(&self, other: &&str) -> bool {
+        self.name == *other
+    }
+}
+
+impl fmt::Display for Ident {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Vcl {
     pub declarations: Vec<Declaration>,
 }
 
+/// Subroutines which Fastly calls, without their `vcl_` prefix.
+pub const BUILTIN_SUBROUTINES: [&str; 9] = [
+    "recv", "hash", "hit", "miss", "pass", "fetch", "error", "deliver", "log",
+];
+
+impl Vcl {
+    /// Builtin subroutines without the `#FASTLY` macro of their name, e.g. a `vcl_recv`
+    /// without `#FASTLY recv`, so Fastly's boilerplate is not expanded into them.
+    pub fn missing_fastly_macros(&self) -> Vec<&SubroutineDeclaration> {
+        self.declarations
+            .iter()
+            .filter_map(|d| match d {
+                Declaration::Subroutine(sub) => Some(sub),
+                _ => None,
+            })
+            .filter(|sub| {
+                sub.builtin_name()
+                    .is_some_and(|name| !has_fastly_macro(&sub.body, name))
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Declaration {
     Include(IncludeDeclaration),
@@ -19,6 +84,36 @@ pub enum Declaration {
     PenaltyBox(PenaltyBoxDeclaration),
     RateCounter(RateCounterDeclaration),
     Table(TableDeclaration),
+    /// Varnish only
+    Version(VersionDeclaration),
+    /// Varnish only
+    Probe(ProbeDeclaration),
+    /// Placeholder for source which could not be parsed in recovering mode
+    Invalid(InvalidDeclaration),
+}
+
+impl Declaration {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Include(d) => d.span,
+            Self::Import(d) => d.span,
+            Self::Subroutine(d) => d.span,
+            Self::Acl(d) => d.span,
+            Self::Backend(d) => d.span,
+            Self::Director(d) => d.span,
+            Self::PenaltyBox(d) => d.span,
+            Self::RateCounter(d) => d.span,
+            Self::Table(d) => d.span,
+            Self::Version(d) => d.span,
+            Self::Probe(d) => d.span,
+            Self::Invalid(d) => d.span,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct InvalidDeclaration {
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -30,35 +125,198 @@ pub struct IncludeDeclaration {
 #[derive(Debug, PartialEq, Clone)]
 pub struct ImportDeclaration {
     pub span: Span,
-    pub ident: String,
+    pub ident: Ident,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct SubroutineDeclaration {
     pub span: Span,
-    pub name: String,
+    pub name: Ident,
     pub return_type: Type,
     pub body: Vec<Statement>,
 }
 
+impl SubroutineDeclaration {
+    /// `recv` for `vcl_recv`, or `None` if this is a custom subroutine.
+    pub fn builtin_name(&self) -> Option<&str> {
+        self.name
+            .as_str()
+            .strip_prefix("vcl_")
+            .filter(|name| BUILTIN_SUBROUTINES.contains(name))
+    }
+}
+
+/// Whether `#FASTLY name` is somewhere in `body`, including nested blocks.
+fn has_fastly_macro(body: &[Statement], name: &str) -> bool {
+    body.iter().any(|statement| match statement {
+        Statement::FastlyMacro(m) => m.name == name,
+        Statement::If(s) => has_fastly_macro_in_if(s, name),
+        _ => false,
+    })
+}
+
+fn has_fastly_macro_in_if(statement: &IfStatement, name: &str) -> bool {
+    has_fastly_macro(&statement.body, name)
+        || match &statement.els {
+            Some(ElseStatement::If(s)) => has_fastly_macro_in_if(s, name),
+            Some(ElseStatement::Body(body)) => has_fastly_macro(body, name),
+            None => false,
+        }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct AclDeclaration {
     pub span: Span,
-    pub name: String,
+    pub name: Ident,
     pub entries: Vec<AclEntry>,
 }
 
+impl AclDeclaration {
+    /// Entries which are duplicates of others or have no effect on what the ACL matches.
+    ///
+    /// The most specific network containing an address decides whether it matches, so an entry
+    /// only matters if the closest larger network around it has the opposite negation.
+    /// Host names are skipped since their addresses are unknown before they are resolved.
+    pub fn overlaps(&self) -> Vec<AclOverlap> {
+        let mut networks: Vec<(usize, &IpNetwork, bool)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| match &entry.addr {
+                AclAddress::Network(network) => Some((i, network, entry.negated)),
+                AclAddress::Host { .. } => None,
+            })
+            .collect();
+        // Networks either nest or are disjoint, so ordered by their first address and larger
+        // ones first, every network is inside those which are still open when it is reached.
+        networks.sort_by_key(|&(i, network, _)| {
+            let first = match network.network() {
+                IpAddr::V4(addr) => u128::from(u32::from(addr)),
+                IpAddr::V6(addr) => u128::from(addr),
+            };
+            (network.addr.is_ipv6(), first, network.prefix, i)
+        });
+        // The earliest entry of each distinct network enclosing the current one
+        let mut open: Vec<(usize, &IpNetwork, bool)> = vec![];
+        let mut overlaps = vec![];
+        for &(i, network, negated) in &networks {
+            while open
+                .last()
+                .is_some_and(|(_, other, _)| !other.contains(network))
+            {
+                open.pop();
+            }
+            let enclosing = open.last().copied();
+            let kind = match enclosing {
+                Some((_, other, other_negated)) if other.prefix == network.prefix => {
+                    if other_negated == negated {
+                        AclOverlapKind::Duplicate
+                    } else {
+                        AclOverlapKind::Conflicting
+                    }
+                }
+                Some((_, _, other_negated)) => {
+                    open.push((i, network, negated));
+                    if other_negated != negated {
+                        continue;
+                    }
+                    AclOverlapKind::Shadowed
+                }
+                None => {
+                    open.push((i, network, negated));
+                    if !negated {
+                        continue;
+                    }
+                    AclOverlapKind::UselessNegation
+                }
+            };
+            overlaps.push(AclOverlap {
+                kind,
+                entry: i,
+                other: enclosing.map(|(j, _, _)| j),
+            });
+        }
+        overlaps.sort_by_key(|overlap| overlap.entry);
+        overlaps
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct BackendDeclaration {
     pub span: Span,
-    pub name: String,
+    pub name: Ident,
     pub config: Object,
+    /// The properties of `config` which could be interpreted
+    pub settings: Box<BackendConfig>,
+}
+
+/// https://developer.fastly.com/reference/vcl/declarations/backend/
+/// and https://varnish-cache.org/docs/trunk/reference/vcl-backend.html
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct BackendConfig {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub host_header: Option<String>,
+    pub always_use_host_header: Option<bool>,
+    pub dynamic: Option<bool>,
+    pub ssl: Option<bool>,
+    pub ssl_cert_hostname: Option<String>,
+    pub ssl_sni_hostname: Option<String>,
+    /// `always` or `never`
+    pub ssl_check_cert: Option<bool>,
+    pub ssl_ca_cert: Option<String>,
+    pub ssl_ciphers: Option<String>,
+    pub min_tls_version: Option<String>,
+    pub max_tls_version: Option<String>,
+    pub connect_timeout: Option<RelativeTime>,
+    pub first_byte_timeout: Option<RelativeTime>,
+    pub between_bytes_timeout: Option<RelativeTime>,
+    pub max_connections: Option<i64>,
+    pub share_key: Option<String>,
+    pub probe: Option<ProbeConfig>,
+    pub ssl_client_cert: Option<String>,
+    pub ssl_client_key: Option<String>,
+    pub prefer_ipv6: Option<bool>,
+    pub bypass_local_route_table: Option<bool>,
+    /// How long an idle connection is kept open for reuse
+    pub keepalive_time: Option<RelativeTime>,
+    pub tcp_keepalive_enable: Option<bool>,
+    pub tcp_keepalive_time: Option<RelativeTime>,
+    pub tcp_keepalive_interval: Option<RelativeTime>,
+    pub tcp_keepalive_probes: Option<i64>,
+    /// `.probe = name;`, a `probe` declaration, in Varnish
+    pub probe_name: Option<Ident>,
+    /// A Unix domain socket instead of `.host`, in Varnish
+    pub path: Option<String>,
+    /// A backend to connect through, in Varnish
+    pub via: Option<Ident>,
+    /// PROXY protocol version 1 or 2, in Varnish
+    pub proxy_header: Option<i64>,
+    pub authority: Option<String>,
+    pub backend_wait_timeout: Option<RelativeTime>,
+    pub backend_wait_limit: Option<i64>,
+}
+
+/// Health check of a backend, `.probe = { ... }`
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ProbeConfig {
+    /// Only resolve the host instead of sending requests
+    pub dummy: Option<bool>,
+    /// Lines of the request, one per string
+    pub request: Option<Vec<String>>,
+    pub url: Option<String>,
+    pub expected_response: Option<i64>,
+    pub interval: Option<RelativeTime>,
+    pub timeout: Option<RelativeTime>,
+    pub window: Option<i64>,
+    pub initial: Option<i64>,
+    pub threshold: Option<i64>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct DirectorDeclaration {
     pub span: Span,
-    pub name: String,
+    pub name: Ident,
     pub typ: DirectorType,
     pub config: Option<Object>,
     pub directions: Vec<Object>,
@@ -67,19 +325,37 @@ pub struct DirectorDeclaration {
 #[derive(Debug, PartialEq, Clone)]
 pub struct PenaltyBoxDeclaration {
     pub span: Span,
-    pub name: String,
+    pub name: Ident,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct RateCounterDeclaration {
     pub span: Span,
-    pub name: String,
+    pub name: Ident,
+}
+
+/// `vcl 4.1;`
+///
+/// https://varnish-cache.org/docs/trunk/reference/vcl.html#versioning
+#[derive(Debug, PartialEq, Clone)]
+pub struct VersionDeclaration {
+    pub span: Span,
+    /// `4.1`
+    pub version: String,
+}
+
+/// https://varnish-cache.org/docs/trunk/reference/vcl-probe.html
+#[derive(Debug, PartialEq, Clone)]
+pub struct ProbeDeclaration {
+    pub span: Span,
+    pub name: Ident,
+    pub config: Object,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct TableDeclaration {
     pub span: Span,
-    pub name: String,
+    pub name: Ident,
     pub typ: Type,
     pub entries: Vec<TableEntry>,
 }
@@ -113,12 +389,120 @@ pub enum DirectorType {
     Unknown(String),
 }
 
+impl DirectorType {
+    pub fn from_keyword(s: &str) -> Self {
+        match s {
+            "random" => Self::Random,
+            "fallback" => Self::Fallback,
+            "hash" => Self::Content,
+            "client" => Self::Client,
+            "chash" => Self::ConsistentHashing,
+            _ => Self::Unknown(s.to_string()),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct AclEntry {
     pub span: Span,
     pub negated: bool,
-    pub addr: String,
-    pub cidr: u8,
+    pub addr: AclAddress,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum AclAddress {
+    /// `"192.0.2.0"/24`, or `"192.0.2.12"` for a single address
+    Network(IpNetwork),
+    /// `"localhost"`, which is resolved when the VCL is loaded
+    Host { name: String, prefix: Option<u8> },
+}
+
+/// The addresses sharing the first `prefix` bits with `addr`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct IpNetwork {
+    pub addr: IpAddr,
+    pub prefix: u8,
+}
+
+impl IpNetwork {
+    /// `None` if `prefix` is longer than the address.
+    pub fn new(addr: IpAddr, prefix: u8) -> Option<Self> {
+        (prefix <= Self::max_prefix(&addr)).then_some(Self { addr, prefix })
+    }
+
+    /// The network of the single address `addr`.
+    pub fn host(addr: IpAddr) -> Self {
+        Self {
+            addr,
+            prefix: Self::max_prefix(&addr),
+        }
+    }
+
+    /// 32 for IPv4 and 128 for IPv6.
+    pub fn max_prefix(addr: &IpAddr) -> u8 {
+        match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        }
+    }
+
+    /// `addr` with the bits after the prefix cleared, e.g. `192.0.2.0` for `192.0.2.12/24`.
+    pub fn network(&self) -> IpAddr {
+        match self.addr {
+            IpAddr::V4(addr) => IpAddr::V4(Ipv4Addr::from(
+                u32::from(addr) & mask(self.prefix, 32) as u32,
+            )),
+            IpAddr::V6(addr) => {
+                IpAddr::V6(Ipv6Addr::from(u128::from(addr) & mask(self.prefix, 128)))
+            }
+        }
+    }
+
+    /// Whether every address of `other` is in this network.
+    pub fn contains(&self, other: &IpNetwork) -> bool {
+        self.addr.is_ipv4() == other.addr.is_ipv4()
+            && self.prefix <= other.prefix
+            && Self {
+                addr: other.addr,
+                prefix: self.prefix,
+            }
+            .network()
+                == self.network()
+    }
+}
+
+impl std::fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// The first `prefix` of `bits` bits set, in the low bits of the result.
+fn mask(prefix: u8, bits: u8) -> u128 {
+    let ones = u128::MAX.checked_shl(u32::from(128 - prefix)).unwrap_or(0);
+    ones >> (128 - bits)
+}
+
+/// An entry of an ACL which duplicates or has no effect because of the other entries.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct AclOverlap {
+    pub kind: AclOverlapKind,
+    /// Index of the entry in `AclDeclaration::entries`
+    pub entry: usize,
+    /// Index of the entry it overlaps with, if any
+    pub other: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum AclOverlapKind {
+    /// The same network as an earlier entry
+    Duplicate,
+    /// The same network as an earlier entry, but with the opposite negation
+    Conflicting,
+    /// Inside a larger network with the same negation and no exception in between
+    Shadowed,
+    /// A negated network outside every other entry, which excludes nothing
+    UselessNegation,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -175,9 +559,24 @@ pub enum Literal {
     Float(FloatLiteral),
     Bool(BoolLiteral),
     RTime(RTimeLiteral),
+    Percentage(PercentageLiteral),
     Object(Object),
 }
 
+impl Literal {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::String(l) => l.span,
+            Self::Integer(l) => l.span,
+            Self::Float(l) => l.span,
+            Self::Bool(l) => l.span,
+            Self::RTime(l) => l.span,
+            Self::Percentage(l) => l.span,
+            Self::Object(l) => l.span,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct StringLiteral {
     pub span: Span,
@@ -187,19 +586,42 @@ pub struct StringLiteral {
 #[derive(Debug, PartialEq, Clone)]
 pub struct StringToken {
     pub span: Span,
+    pub kind: StringKind,
+    /// Contents with escapes decoded
     pub value: String,
+    /// The token as written, including quotes
+    pub raw: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum StringKind {
+    /// `"..."`, where `%XX`, `%uXXXX` and `%u{X...}` are escapes
+    Quoted,
+    /// `""`, `{""}` or `{DELIM""DELIM}`; `raw` tells which
+    Empty,
+    /// `{"..."}`, without escapes
+    Braces,
+    /// `{DELIM"..."DELIM}`, without escapes
+    Heredoc {
+        /// `DELIM`
+        delimiter: String,
+    },
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct IntegerLiteral {
     pub span: Span,
     pub value: i64,
+    /// The literal as written, e.g. `0x1F`
+    pub raw: String,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct FloatLiteral {
     pub span: Span,
     pub value: f64,
+    /// The literal as written, e.g. `1e3` or `0x1.8p3`
+    pub raw: String,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -214,74 +636,217 @@ pub struct RTimeLiteral {
     pub value: RelativeTime,
 }
 
+/// `50%`, only allowed as an object value (e.g. director `.quorum`)
+#[derive(Debug, PartialEq, Clone)]
+pub struct PercentageLiteral {
+    pub span: Span,
+    pub value: f64,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Object {
-    pub entries: Vec<(String, ObjectValue)>,
+    pub entries: Vec<(Ident, ObjectValue)>,
     pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum ObjectValue {
     Literal(Literal),
-    Ident(String),
+    Ident(Ident),
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// RTIME value, a signed duration with nanosecond precision.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct RelativeTime {
-    ms: u64,
+    ns: i64,
 }
+
+/// Units of RTIME literals with their length in nanoseconds, from the largest.
+const TIME_UNITS: [(&str, i64); 6] = [
+    ("y", 365 * 24 * 60 * 60 * 1_000_000_000),
+    ("d", 24 * 60 * 60 * 1_000_000_000),
+    ("h", 60 * 60 * 1_000_000_000),
+    ("m", 60 * 1_000_000_000),
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+];
+
 impl RelativeTime {
+    pub const ZERO: Self = Self { ns: 0 };
+
+    #[inline]
+    pub fn from_nanos(ns: i64) -> Self {
+        Self { ns }
+    }
+    /// Values out of range saturate, as do the other `from_*` constructors.
     #[inline]
-    pub fn from_ms(ms: u64) -> Self {
-        Self { ms }
+    pub fn from_ms(ms: f64) -> Self {
+        Self::from_unit(ms, 1_000_000)
     }
     #[inline]
     pub fn from_sec(sec: f64) -> Self {
-        Self::from_ms((sec * 1000.0) as u64)
+        Self::from_unit(sec, 1_000_000_000)
     }
     #[inline]
     pub fn from_min(min: f64) -> Self {
-        Self::from_sec(min * 60.0)
+        Self::from_unit(min, 60 * 1_000_000_000)
     }
     #[inline]
     pub fn from_hour(hour: f64) -> Self {
-        Self::from_min(hour * 60.0)
+        Self::from_unit(hour, 60 * 60 * 1_000_000_000)
     }
     #[inline]
     pub fn from_day(day: f64) -> Self {
-        Self::from_hour(day * 24.0)
+        Self::from_unit(day, 24 * 60 * 60 * 1_000_000_000)
     }
     #[inline]
     pub fn from_year(year: f64) -> Self {
-        Self::from_day(year * 365.0)
+        Self::from_unit(year, 365 * 24 * 60 * 60 * 1_000_000_000)
+    }
+    fn from_unit(value: f64, ns: i64) -> Self {
+        Self::from_nanos((value * ns as f64).round() as i64)
+    }
+
+    #[inline]
+    pub fn as_nanos(&self) -> i64 {
+        self.ns
+    }
+    #[inline]
+    pub fn as_ms(&self) -> f64 {
+        self.ns as f64 / 1_000_000.0
+    }
+    #[inline]
+    pub fn as_secs(&self) -> f64 {
+        self.ns as f64 / 1_000_000_000.0
+    }
+    #[inline]
+    pub fn is_negative(&self) -> bool {
+        self.ns < 0
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.ns.checked_add(rhs.ns).map(Self::from_nanos)
+    }
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.ns.checked_sub(rhs.ns).map(Self::from_nanos)
+    }
+    pub fn checked_mul(self, rhs: i64) -> Option<Self> {
+        self.ns.checked_mul(rhs).map(Self::from_nanos)
+    }
+    pub fn checked_div(self, rhs: i64) -> Option<Self> {
+        self.ns.checked_div(rhs).map(Self::from_nanos)
+    }
+    pub fn checked_neg(self) -> Option<Self> {
+        self.ns.checked_neg().map(Self::from_nanos)
+    }
+}
+
+/// The shortest VCL literal: the largest unit dividing the value, or fractional milliseconds.
+impl std::fmt::Display for RelativeTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ns == 0 {
+            return f.write_str("0s");
+        }
+        let sign = if self.ns < 0 { "-" } else { "" };
+        let ns = self.ns.unsigned_abs();
+        for (unit, unit_ns) in TIME_UNITS {
+            if ns.is_multiple_of(unit_ns as u64) {
+                return write!(f, "{}{}{}", sign, ns / unit_ns as u64, unit);
+            }
+        }
+        let fraction = format!("{:06}", ns % 1_000_000);
+        write!(
+            f,
+            "{}{}.{}ms",
+            sign,
+            ns / 1_000_000,
+            fraction.trim_end_matches('0')
+        )
+    }
+}
+
+/// Parses an RTIME literal such as `10s`, `-1.5h` or `0.5ms`, failing if it is out of range.
+impl FromStr for RelativeTime {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number_len = s
+            .bytes()
+            .take_while(|b| b.is_ascii_digit() || matches!(b, b'-' | b'.'))
+            .count();
+        let (number, unit) = s.split_at(number_len);
+        let unit_ns = TIME_UNITS
+            .iter()
+            .find(|(name, _)| *name == unit)
+            .map(|(_, ns)| i128::from(*ns))
+            .ok_or(())?;
+        let (negative, number) = match number.strip_prefix('-') {
+            Some(number) => (true, number),
+            None => (false, number),
+        };
+        let (int, fraction) = number.split_once('.').unwrap_or((number, ""));
+        let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if int.is_empty() || !is_digits(int) || !is_digits(fraction) {
+            return Err(());
+        }
+        // Digits beyond the 18th fractional one are below a nanosecond for every unit.
+        let fraction = &fraction[..fraction.len().min(18)];
+        let scale = 10i128.pow(fraction.len() as u32);
+        let mantissa = format!("{}{}", int, fraction)
+            .parse::<i128>()
+            .map_err(|_| ())?;
+        let ns = mantissa.checked_mul(unit_ns).ok_or(())?;
+        // Round half away from zero to whole nanoseconds.
+        let ns = ns.checked_add(scale / 2).ok_or(())? / scale;
+        let ns = i64::try_from(if negative { -ns } else { ns }).map_err(|_| ())?;
+        Ok(Self::from_nanos(ns))
+    }
+}
+
+/// Saturates at `i64::MIN` / `i64::MAX` nanoseconds; use the `checked_*` methods to detect
+/// overflow.
+impl std::ops::Neg for RelativeTime {
+    type Output = Self;
+    #[inline]
+    fn neg(self) -> Self::Output {
+        Self::from_nanos(self.ns.saturating_neg())
     }
 }
+/// Saturates, see `Neg`.
 impl std::ops::Add for RelativeTime {
     type Output = Self;
     #[inline]
     fn add(self, rhs: Self) -> Self::Output {
-        Self::from_ms(self.ms + rhs.ms)
+        Self::from_nanos(self.ns.saturating_add(rhs.ns))
     }
 }
+/// Saturates, see `Neg`.
 impl std::ops::Sub for RelativeTime {
     type Output = Self;
     #[inline]
     fn sub(self, rhs: Self) -> Self::Output {
-        Self::from_ms(self.ms - rhs.ms)
+        Self::from_nanos(self.ns.saturating_sub(rhs.ns))
     }
 }
-impl std::ops::Mul<u64> for RelativeTime {
+/// Saturates, see `Neg`.
+impl std::ops::Mul<i64> for RelativeTime {
     type Output = Self;
     #[inline]
-    fn mul(self, rhs: u64) -> Self::Output {
-        Self::from_ms(self.ms * rhs)
+    fn mul(self, rhs: i64) -> Self::Output {
+        Self::from_nanos(self.ns.saturating_mul(rhs))
     }
 }
-impl std::ops::Div<u64> for RelativeTime {
+/// Saturates, see `Neg`. Dividing by zero gives the extreme with the sign of `self`, or zero.
+impl std::ops::Div<i64> for RelativeTime {
     type Output = Self;
     #[inline]
-    fn div(self, rhs: u64) -> Self::Output {
-        Self::from_ms(self.ms / rhs)
+    fn div(self, rhs: i64) -> Self::Output {
+        match (self.ns, rhs) {
+            (0, 0) => Self::ZERO,
+            (ns, 0) if ns < 0 => Self::from_nanos(i64::MIN),
+            (_, 0) => Self::from_nanos(i64::MAX),
+            (ns, rhs) => Self::from_nanos(ns.saturating_div(rhs)),
+        }
     }
 }
 
@@ -295,11 +860,23 @@ pub enum Statement {
     Declare(DeclareStatement),
     Error(ErrorStatement),
     Esi(EsiStatement),
+    Goto(GotoStatement),
+    Label(LabelStatement),
     Include(IncludeStatement),
     Log(LogStatement),
     Restart(RestartStatement),
     Return(ReturnStatement),
     Synthetic(SyntheticStatement),
+    FastlyMacro(FastlyMacroStatement),
+    /// Varnish only
+    New(NewStatement),
+    /// Placeholder for source which could not be parsed in recovering mode
+    Invalid(InvalidStatement),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct InvalidStatement {
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -406,6 +983,19 @@ pub struct EsiStatement {
     pub span: Span,
 }
 
+#[derive(Debug, PartialEq, Clone)]
+pub struct GotoStatement {
+    pub span: Span,
+    pub label: Ident,
+}
+
+/// `name:`, the target of a `goto`
+#[derive(Debug, PartialEq, Clone)]
+pub struct LabelStatement {
+    pub span: Span,
+    pub name: Ident,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct IncludeStatement {
     pub span: Span,
@@ -426,7 +1016,77 @@ pub struct RestartStatement {
 #[derive(Debug, PartialEq, Clone)]
 pub struct ReturnStatement {
     pub span: Span,
-    pub value: Option<Expression>,
+    pub value: Option<ReturnValue>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ReturnValue {
+    /// `return(pass);`, which moves the request to the next state, in subroutines without a
+    /// return type
+    Action(ReturnAction),
+    /// `return "foo";` in subroutines with a return type
+    Expression(Expression),
+}
+
+/// https://developer.fastly.com/reference/vcl/subroutines/
+/// and https://varnish-cache.org/docs/trunk/users-guide/vcl-built-in-subs.html
+#[derive(Debug, PartialEq, Clone)]
+pub enum ReturnAction {
+    Lookup,
+    Pass,
+    Deliver,
+    /// Fastly only
+    DeliverStale,
+    Fetch,
+    Hash,
+    Restart,
+    /// Fastly only
+    Error,
+    /// Fastly only
+    Upgrade,
+    /// Varnish only
+    Pipe,
+    /// Varnish only
+    Purge,
+    /// `synth(status, reason)`, Varnish only
+    Synth(Vec<Expression>),
+    /// Varnish only
+    Miss,
+    /// Varnish only
+    Fail,
+    /// Varnish only
+    Abandon,
+    /// Varnish only
+    Retry,
+    /// Varnish only
+    Ok,
+    /// fallback
+    Unknown(String),
+}
+
+impl ReturnAction {
+    pub fn from_keyword(s: &str) -> Self {
+        match s {
+            "lookup" => Self::Lookup,
+            "pass" => Self::Pass,
+            "deliver" => Self::Deliver,
+            "deliver_stale" => Self::DeliverStale,
+            "fetch" => Self::Fetch,
+            "hash" => Self::Hash,
+            "restart" => Self::Restart,
+            "error" => Self::Error,
+            "upgrade" => Self::Upgrade,
+            "pipe" => Self::Pipe,
+            "purge" => Self::Purge,
+            "synth" => Self::Synth(vec![]),
+            "miss" => Self::Miss,
+            "fail" => Self::Fail,
+            "abandon" => Self::Abandon,
+            "retry" => Self::Retry,
+            "ok" => Self::Ok,
+            _ => Self::Unknown(s.to_string()),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -436,6 +1096,24 @@ pub struct SyntheticStatement {
     pub base64: bool,
 }
 
+/// `#FASTLY recv`, where Fastly inserts its boilerplate for the subroutine
+#[derive(Debug, PartialEq, Clone)]
+pub struct FastlyMacroStatement {
+    pub span: Span,
+    /// `recv`
+    pub name: Ident,
+}
+
+/// `new cluster = directors.round_robin();`
+///
+/// https://varnish-cache.org/docs/trunk/reference/vcl.html#vcl-objects
+#[derive(Debug, PartialEq, Clone)]
+pub struct NewStatement {
+    pub span: Span,
+    pub name: Ident,
+    pub constructor: CallExpression,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expression {
     Literal(Literal),
@@ -443,6 +1121,22 @@ pub enum Expression {
     Binary(BinaryExpression),
     Unary(UnaryExpression),
     Call(CallExpression),
+    Concat(ConcatExpression),
+    Paren(ParenExpression),
+}
+
+impl Expression {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Literal(e) => e.span(),
+            Self::Variable(e) => e.span,
+            Self::Binary(e) => e.span,
+            Self::Unary(e) => e.span,
+            Self::Call(e) => e.span,
+            Self::Concat(e) => e.span,
+            Self::Paren(e) => e.span,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -455,9 +1149,12 @@ pub struct Variable {
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct BinaryExpression {
+    /// From the start of `lhs` to the end of `rhs`
     pub span: Span,
     pub lhs: Box<Expression>,
     pub operator: BinaryOperator,
+    /// The operator token
+    pub operator_span: Span,
     pub rhs: Box<Expression>,
 }
 
@@ -495,11 +1192,31 @@ pub enum BinaryOperator {
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct UnaryExpression {
+    /// From the operator to the end of `rhs`
     pub span: Span,
     pub operator: UnaryOperator,
+    pub operator_span: Span,
     pub rhs: Box<Expression>,
 }
 
+/// Juxtaposed strings, variables and calls, e.g. `"a" req.http.b std.tolower("c")`, which
+/// concatenate like `+` without an operator.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ConcatExpression {
+    /// From the start of the first part to the end of the last
+    pub span: Span,
+    /// At least two
+    pub parts: Vec<Expression>,
+}
+
+/// `(expr)`
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParenExpression {
+    /// Including the parentheses
+    pub span: Span,
+    pub expr: Box<Expression>,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum UnaryOperator {
     /// `!` (Logical NOT)
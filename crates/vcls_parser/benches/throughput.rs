@@ -0,0 +1,73 @@
+//! Parser throughput on large generated sources: `cargo bench -p vcls_parser`.
+
+use std::{
+    fmt::Write,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+fn acl(entries: usize) -> String {
+    let mut src = "acl big {\n".to_string();
+    for i in 0..entries {
+        let negated = if i % 7 == 0 { "!" } else { "" };
+        writeln!(
+            src,
+            "  {}\"10.{}.{}.0\"/24; # entry {}",
+            negated,
+            i / 256 % 256,
+            i % 256,
+            i
+        )
+        .unwrap();
+    }
+    src.push_str("}\n");
+    src
+}
+
+fn table(entries: usize) -> String {
+    let mut src = "table big STRING {\n".to_string();
+    for i in 0..entries {
+        writeln!(src, "  \"/path/{}\": \"https://example.com/{}\",", i, i).unwrap();
+    }
+    src.push_str("}\n");
+    src
+}
+
+fn sub(statements: usize) -> String {
+    let mut src = "sub vcl_recv {\n".to_string();
+    for i in 0..statements {
+        writeln!(
+            src,
+            "  if (req.http.X-{} == \"a\" && std.strlen(req.url) > {}) {{ set req.http.Y = \"b\" req.http.X-{}; }}",
+            i, i, i
+        )
+        .unwrap();
+    }
+    src.push_str("}\n");
+    src
+}
+
+fn bench(name: &str, src: &str) {
+    vcls_parser::parse(src).expect("generated source must parse");
+    let start = Instant::now();
+    let mut iterations = 0u32;
+    while start.elapsed() < Duration::from_secs(1) {
+        black_box(vcls_parser::parse(black_box(src)).ok());
+        iterations += 1;
+    }
+    let per_iteration = start.elapsed() / iterations;
+    let throughput = src.len() as f64 / per_iteration.as_secs_f64() / 1_000_000.0;
+    println!(
+        "{:<6} {:>10} bytes {:>12.2?}/iter {:>8.1} MB/s",
+        name,
+        src.len(),
+        per_iteration,
+        throughput
+    );
+}
+
+fn main() {
+    bench("acl", &acl(50_000));
+    bench("table", &table(50_000));
+    bench("sub", &sub(10_000));
+}
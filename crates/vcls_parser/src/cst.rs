@@ -0,0 +1,269 @@
+//! Lossless concrete syntax tree.
+//!
+//! Every byte of the source belongs to exactly one token, including whitespace and comments,
+//! so printing the tree reproduces the source. Nodes group tokens by construct, and the typed
+//! `vcls_ast` is a view over the tree: `SyntaxTree::covering_node(ast_node.span)` finds the
+//! syntax (and with it the comments) behind an AST node.
+
+use std::fmt;
+
+use vcls_ast::{Span, Vcl};
+
+use crate::lexer::{tokenize, Token, TokenKind};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum SyntaxKind {
+    Root,
+    IncludeDeclaration,
+    ImportDeclaration,
+    SubroutineDeclaration,
+    AclDeclaration,
+    AclEntry,
+    BackendDeclaration,
+    DirectorDeclaration,
+    PenaltyBoxDeclaration,
+    RateCounterDeclaration,
+    TableDeclaration,
+    TableEntry,
+    VersionDeclaration,
+    ProbeDeclaration,
+    Block,
+    Object,
+    ObjectEntry,
+    IfStatement,
+    ElseClause,
+    SetStatement,
+    UnsetStatement,
+    AddStatement,
+    CallStatement,
+    DeclareStatement,
+    ErrorStatement,
+    EsiStatement,
+    GotoStatement,
+    LabelStatement,
+    IncludeStatement,
+    LogStatement,
+    RestartStatement,
+    ReturnStatement,
+    SyntheticStatement,
+    FastlyMacroStatement,
+    NewStatement,
+    Expression,
+    ParenExpression,
+    CallExpression,
+    Arguments,
+    Variable,
+    Literal,
+    /// Source which could not be parsed
+    Invalid,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(Token),
+}
+
+impl SyntaxElement {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Node(node) => node.span,
+            Self::Token(token) => token.span,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub span: Span,
+    pub children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    pub fn child_nodes(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// All tokens below this node, in source order.
+    pub fn tokens(&self) -> Vec<&Token> {
+        let mut tokens = vec![];
+        self.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a Token>) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.collect_tokens(tokens),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+    }
+
+    /// This node and all nodes below it, in pre-order.
+    pub fn descendants(&self) -> Vec<&SyntaxNode> {
+        let mut nodes = vec![self];
+        let mut i = 0;
+        while i < nodes.len() {
+            let node = nodes[i];
+            nodes.splice(i + 1..i + 1, node.child_nodes());
+            i += 1;
+        }
+        nodes
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SyntaxTree {
+    source: String,
+    root: SyntaxNode,
+    vcl: Vcl,
+}
+
+impl SyntaxTree {
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn root(&self) -> &SyntaxNode {
+        &self.root
+    }
+
+    /// The typed AST of the same source.
+    pub fn vcl(&self) -> &Vcl {
+        &self.vcl
+    }
+
+    pub fn text(&self, span: Span) -> &str {
+        &self.source[span.0..span.1]
+    }
+
+    /// The innermost node containing `span`, e.g. the node behind an AST node.
+    pub fn covering_node(&self, span: Span) -> &SyntaxNode {
+        let mut node = &self.root;
+        while let Some(child) = node
+            .child_nodes()
+            .find(|child| child.span.0 <= span.0 && span.1 <= child.span.1)
+        {
+            node = child;
+        }
+        node
+    }
+
+    /// Comments directly preceding `span`, only separated by whitespace.
+    pub fn leading_comments(&self, span: Span) -> Vec<&Token> {
+        let tokens = self.root.tokens();
+        let end = tokens.partition_point(|t| t.span.1 <= span.0);
+        let mut comments: Vec<&Token> = tokens[..end]
+            .iter()
+            .rev()
+            .take_while(|t| t.is_trivia())
+            .filter(|t| t.kind != TokenKind::Whitespace)
+            .copied()
+            .collect();
+        comments.reverse();
+        comments
+    }
+}
+
+impl fmt::Display for SyntaxTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in self.root.tokens() {
+            f.write_str(token.text(&self.source))?;
+        }
+        Ok(())
+    }
+}
+
+/// Trivia which is moved out of the edges of nodes; a `#FASTLY` macro is kept since it can
+/// be a statement node of its own.
+fn is_edge_trivia(token: &Token) -> bool {
+    token.is_trivia() && token.kind != TokenKind::FastlyMacro
+}
+
+/// Assembles a tree from node boundaries reported by the parser.
+///
+/// Tokens are assigned to nodes by position, so the parser only has to report where each
+/// construct starts and ends. Trivia at the edges of a node is left to its parent, so comments
+/// before a statement are siblings of the statement rather than part of it.
+pub struct SyntaxTreeBuilder<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    next: usize,
+    stack: Vec<(SyntaxKind, Vec<SyntaxElement>)>,
+}
+
+impl<'a> SyntaxTreeBuilder<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Self::with_tokens(source, tokenize(source))
+    }
+
+    pub(crate) fn with_tokens(source: &'a str, tokens: Vec<Token>) -> Self {
+        Self {
+            source,
+            tokens,
+            next: 0,
+            stack: vec![(SyntaxKind::Root, vec![])],
+        }
+    }
+
+    pub fn start_node(&mut self, kind: SyntaxKind, start: usize) {
+        self.push_tokens_until(start);
+        self.stack.push((kind, vec![]));
+    }
+
+    pub fn finish_node(&mut self, end: usize) {
+        self.push_tokens_until(end);
+        let (kind, mut children) = self.stack.pop().expect("unbalanced finish_node");
+        let trailing = children
+            .iter()
+            .rev()
+            .take_while(|c| matches!(c, SyntaxElement::Token(t) if is_edge_trivia(t)))
+            .count();
+        let trailing = children.split_off(children.len() - trailing);
+        let leading = children
+            .iter()
+            .take_while(|c| matches!(c, SyntaxElement::Token(t) if is_edge_trivia(t)))
+            .count();
+        let content: Vec<SyntaxElement> = children.split_off(leading);
+        let parent = &mut self.stack.last_mut().expect("finish_node on root").1;
+        parent.extend(children);
+        if let (Some(first), Some(last)) = (content.first(), content.last()) {
+            parent.push(SyntaxElement::Node(SyntaxNode {
+                kind,
+                span: Span(first.span().0, last.span().1),
+                children: content,
+            }));
+        }
+        parent.extend(trailing);
+    }
+
+    pub fn finish(mut self, vcl: Vcl) -> SyntaxTree {
+        self.push_tokens_until(self.source.len());
+        while self.stack.len() > 1 {
+            self.finish_node(self.source.len());
+        }
+        let (_, children) = self.stack.pop().unwrap();
+        SyntaxTree {
+            source: self.source.to_string(),
+            root: SyntaxNode {
+                kind: SyntaxKind::Root,
+                span: Span(0, self.source.len()),
+                children,
+            },
+            vcl,
+        }
+    }
+
+    fn push_tokens_until(&mut self, pos: usize) {
+        let children = &mut self.stack.last_mut().unwrap().1;
+        while let Some(token) = self.tokens.get(self.next).filter(|t| t.span.1 <= pos) {
+            children.push(SyntaxElement::Token(*token));
+            self.next += 1;
+        }
+    }
+}
@@ -1,100 +1,118 @@
-use std::vec;
+use std::net::IpAddr;
 
-use pest::iterators::Pair;
-use vcls_ast::{AclDeclaration, AclEntry, Span};
+use vcls_ast::{AclAddress, AclDeclaration, AclEntry, IpNetwork, Span};
 
 use crate::{
-    error::ParseError,
-    utils::{convert_span, remove_quotes},
-    ParseResult, Rule,
+    cst::SyntaxKind,
+    error::{ErrorCode, ParseError},
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+    utils::remove_quotes,
 };
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<AclDeclaration> {
-    let mut errors = vec![];
-    let span = convert_span(pair.as_span());
-    let mut inner = pair.into_inner();
-    let name = inner
-        .find(|p| p.as_rule() == Rule::Ident)
-        .unwrap()
-        .as_str()
-        .to_string();
-    let entries: Vec<AclEntry> = inner
-        .filter_map(|p: Pair<'_, Rule>| match p.as_rule() {
-            Rule::AclEntryValue => match handle_acl_entry(p) {
-                Ok(entry) => Some(entry),
-                Err(e) => {
-                    errors.extend(e);
-                    None
-                }
-            },
-            _ => None,
-        })
-        .collect();
-    if errors.is_empty() {
-        Ok(AclDeclaration {
-            name,
-            entries,
-            span,
-        })
-    } else {
-        Err(errors)
+pub fn parse(p: &mut Parser) -> SyntaxResult<AclDeclaration> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword("acl")?;
+    let name = p.expect_ident()?;
+    p.expect(TokenKind::LBrace, "{")?;
+    let mut entries = vec![];
+    loop {
+        p.skip_trivia();
+        if p.eat(TokenKind::RBrace).is_some() {
+            break;
+        }
+        if !p.at(TokenKind::Not) && !p.at(TokenKind::QuotedString) {
+            return Err(p.unexpected(&["String", "}"]));
+        }
+        entries.push(p.node(SyntaxKind::AclEntry, parse_entry)?);
+        p.expect(TokenKind::Semicolon, ";")?;
     }
+    Ok(AclDeclaration {
+        name,
+        entries,
+        span: Span(start, p.offset()),
+    })
 }
 
-#[derive(Debug)]
-struct AclEntryBuilder {
-    nagated: bool,
-    addr: Option<String>,
-    cidr: u8,
-    span: Span,
-}
-impl AclEntryBuilder {
-    fn new(span: Span) -> Self {
-        Self {
-            nagated: false,
-            addr: None,
-            cidr: 0,
-            span,
+/// `!"192.168.0.0"/16`, without the `;`
+fn parse_entry(p: &mut Parser) -> SyntaxResult<AclEntry> {
+    p.skip_trivia();
+    let start = p.offset();
+    let negated = p.eat(TokenKind::Not).is_some();
+    let token = p.expect(TokenKind::QuotedString, "String")?;
+    let text = remove_quotes(p.text(token));
+    p.skip_trivia();
+    let prefix = if p.eat(TokenKind::Slash).is_some() {
+        let value = p.expect(TokenKind::Number, "Number")?;
+        Some((value.span, p.text(value)))
+    } else {
+        None
+    };
+    let addr = match text.parse::<IpAddr>() {
+        Ok(ip) => AclAddress::Network(match prefix {
+            None => IpNetwork::host(ip),
+            Some((span, prefix)) => {
+                let network = prefix
+                    .parse()
+                    .ok()
+                    .and_then(|prefix| IpNetwork::new(ip, prefix));
+                network.unwrap_or_else(|| {
+                    p.errors.push(invalid_prefix(span, prefix, &ip));
+                    IpNetwork::host(ip)
+                })
+            }
+        }),
+        Err(_) => {
+            if !is_host_name(&text) {
+                p.errors.push(ParseError::new(
+                    ErrorCode::InvalidAclEntry,
+                    token.span,
+                    format!("Invalid IP address: {}", text),
+                ));
+            }
+            let prefix = prefix.and_then(|(span, prefix)| {
+                prefix
+                    .parse()
+                    .ok()
+                    .filter(|&prefix| prefix <= 128)
+                    .or_else(|| {
+                        p.errors.push(ParseError::new(
+                            ErrorCode::InvalidAclEntry,
+                            span,
+                            format!("Invalid CIDR: {}", prefix),
+                        ));
+                        None
+                    })
+            });
+            AclAddress::Host { name: text, prefix }
         }
-    }
-    fn negated(&mut self) {
-        self.nagated = true;
-    }
-    fn addr(&mut self, addr: String) {
-        self.addr = Some(addr);
-    }
-    fn cidr(&mut self, cidr: u8) {
-        self.cidr = cidr;
-    }
+    };
+    Ok(AclEntry {
+        negated,
+        addr,
+        span: Span(start, p.offset()),
+    })
 }
-impl TryInto<AclEntry> for AclEntryBuilder {
-    type Error = ParseError;
-    fn try_into(self) -> Result<AclEntry, Self::Error> {
-        if let Some(addr) = self.addr {
-            Ok(AclEntry {
-                negated: self.nagated,
-                addr,
-                cidr: self.cidr,
-                span: self.span,
-            })
-        } else {
-            Err(ParseError {
-                message: "ACL entry address not found".to_string(),
-            })
-        }
-    }
+
+fn invalid_prefix(span: Span, prefix: &str, ip: &IpAddr) -> ParseError {
+    let (family, max) = match ip {
+        IpAddr::V4(_) => ("IPv4", 32),
+        IpAddr::V6(_) => ("IPv6", 128),
+    };
+    ParseError::new(
+        ErrorCode::InvalidAclEntry,
+        span,
+        format!("Invalid CIDR for an {} address: {}", family, prefix),
+    )
+    .with_note(format!("{} prefixes are 0 to {}", family, max))
 }
 
-fn handle_acl_entry(pair: Pair<Rule>) -> ParseResult<AclEntry> {
-    let mut builder = AclEntryBuilder::new(convert_span(pair.as_span()));
-    for pair in pair.into_inner() {
-        match pair.as_rule() {
-            Rule::AclEntryNegated => builder.negated(),
-            Rule::QuotedString => builder.addr(remove_quotes(pair.as_str())),
-            Rule::AclEntryCidrValue => builder.cidr(pair.as_str().parse().unwrap()),
-            Rule::COMMENT => {}
-            _ => unreachable!(),
-        }
-    }
-    builder.try_into().map_err(|e| vec![e])
+/// `localhost`, but not `1920.2.0`, which is meant to be an IPv4 address.
+fn is_host_name(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'.')
+        && text.bytes().any(|b| b.is_ascii_alphabetic())
 }
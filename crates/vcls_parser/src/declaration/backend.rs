@@ -1,20 +1,387 @@
-use pest::iterators::Pair;
-use vcls_ast::BackendDeclaration;
-
-use crate::{literal::object, utils::convert_span, ParseResult, Rule};
-
-pub fn handle(pair: Pair<Rule>) -> ParseResult<BackendDeclaration> {
-    let span = convert_span(pair.as_span());
-    let mut inner = pair.into_inner();
-    let name = inner
-        .find(|p| p.as_rule() == Rule::Ident)
-        .unwrap()
-        .as_str()
-        .to_string();
-    let obj = inner.find(|p| p.as_rule() == Rule::Object).unwrap();
+use vcls_ast::{
+    BackendConfig, BackendDeclaration, Declaration, Ident, Literal, Object, ObjectValue,
+    ProbeConfig, RelativeTime, Span, Vcl,
+};
+
+use crate::{
+    error::{ErrorCode, ParseError, Severity},
+    literal::object::{self, EntrySpan},
+    parser::{Dialect, Parser, SyntaxResult},
+};
+
+const PROPERTIES: [&str; 28] = [
+    "host",
+    "port",
+    "host_header",
+    "always_use_host_header",
+    "dynamic",
+    "ssl",
+    "ssl_cert_hostname",
+    "ssl_sni_hostname",
+    "ssl_check_cert",
+    "ssl_ca_cert",
+    "ssl_ciphers",
+    "min_tls_version",
+    "max_tls_version",
+    "connect_timeout",
+    "first_byte_timeout",
+    "between_bytes_timeout",
+    "max_connections",
+    "share_key",
+    "probe",
+    "ssl_client_cert",
+    "ssl_client_key",
+    "prefer_ipv6",
+    "bypass_local_route_table",
+    "keepalive_time",
+    "tcp_keepalive_enable",
+    "tcp_keepalive_time",
+    "tcp_keepalive_interval",
+    "tcp_keepalive_probes",
+];
+
+/// Those of Varnish which Fastly does not have
+const VARNISH_PROPERTIES: [&str; 6] = [
+    "path",
+    "via",
+    "proxy_header",
+    "authority",
+    "backend_wait_timeout",
+    "backend_wait_limit",
+];
+
+const PROBE_PROPERTIES: [&str; 9] = [
+    "dummy",
+    "request",
+    "url",
+    "expected_response",
+    "interval",
+    "timeout",
+    "window",
+    "initial",
+    "threshold",
+];
+
+pub fn parse(p: &mut Parser) -> SyntaxResult<BackendDeclaration> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword("backend")?;
+    let name = p.expect_ident()?;
+    let (config, spans) = object::parse_spanned(p)?;
+    let mut entries = Entries {
+        errors: vec![],
+        span: Span(0, 0),
+        dialect: p.dialect,
+    };
+    let settings = entries.backend(&config, &spans);
+    p.errors.extend(entries.errors);
     Ok(BackendDeclaration {
         name,
-        config: object::handle(obj)?,
-        span,
+        config,
+        settings: Box::new(settings),
+        span: Span(start, p.offset()),
     })
 }
+
+/// Interprets the entries of a backend, collecting diagnostics for those which cannot be.
+struct Entries {
+    errors: Vec<ParseError>,
+    /// The entry being interpreted
+    span: Span,
+    dialect: Dialect,
+}
+
+impl Entries {
+    fn backend(&mut self, config: &Object, spans: &[EntrySpan]) -> BackendConfig {
+        let mut settings = BackendConfig::default();
+        let mut ssl_span = None;
+        let mut hostname_spans = vec![];
+        for ((key, value), entry) in config.entries.iter().zip(spans) {
+            self.span = entry.span;
+            let key = key.as_str();
+            match key {
+                "host" => settings.host = self.string(key, value),
+                "port" => settings.port = self.port(key, value),
+                "host_header" => settings.host_header = self.string(key, value),
+                "always_use_host_header" => settings.always_use_host_header = self.bool(key, value),
+                "dynamic" => settings.dynamic = self.bool(key, value),
+                "ssl" => {
+                    settings.ssl = self.bool(key, value);
+                    ssl_span = Some(entry.span);
+                }
+                "ssl_cert_hostname" => {
+                    settings.ssl_cert_hostname = self.string(key, value);
+                    hostname_spans.push((key, entry.span));
+                }
+                "ssl_sni_hostname" => {
+                    settings.ssl_sni_hostname = self.string(key, value);
+                    hostname_spans.push((key, entry.span));
+                }
+                "ssl_check_cert" => settings.ssl_check_cert = self.check_cert(key, value),
+                "ssl_ca_cert" => settings.ssl_ca_cert = self.string(key, value),
+                "ssl_ciphers" => settings.ssl_ciphers = self.string(key, value),
+                "min_tls_version" => settings.min_tls_version = self.string(key, value),
+                "max_tls_version" => settings.max_tls_version = self.string(key, value),
+                "connect_timeout" => settings.connect_timeout = self.rtime(key, value),
+                "first_byte_timeout" => settings.first_byte_timeout = self.rtime(key, value),
+                "between_bytes_timeout" => settings.between_bytes_timeout = self.rtime(key, value),
+                "max_connections" => settings.max_connections = self.integer(key, value),
+                "share_key" => settings.share_key = self.string(key, value),
+                "probe" => match value {
+                    ObjectValue::Literal(Literal::Object(probe)) => {
+                        settings.probe = Some(self.probe(probe, &entry.entries))
+                    }
+                    ObjectValue::Ident(name) if self.dialect == Dialect::Varnish => {
+                        settings.probe_name = Some(name.clone())
+                    }
+                    _ if self.dialect == Dialect::Varnish => {
+                        self.invalid::<()>(key, "an object or the name of a probe");
+                    }
+                    _ => settings.probe = self.invalid(key, "an object"),
+                },
+                "ssl_client_cert" => settings.ssl_client_cert = self.string(key, value),
+                "ssl_client_key" => settings.ssl_client_key = self.string(key, value),
+                "prefer_ipv6" => settings.prefer_ipv6 = self.bool(key, value),
+                "bypass_local_route_table" => {
+                    settings.bypass_local_route_table = self.bool(key, value)
+                }
+                "keepalive_time" => settings.keepalive_time = self.rtime(key, value),
+                "tcp_keepalive_enable" => settings.tcp_keepalive_enable = self.bool(key, value),
+                "tcp_keepalive_time" => settings.tcp_keepalive_time = self.rtime(key, value),
+                "tcp_keepalive_interval" => {
+                    settings.tcp_keepalive_interval = self.rtime(key, value)
+                }
+                "tcp_keepalive_probes" => settings.tcp_keepalive_probes = self.integer(key, value),
+                _ if self.dialect == Dialect::Varnish => self.varnish(&mut settings, key, value),
+                _ => self.unknown(key, &PROPERTIES),
+            }
+        }
+
+        let ssl = settings.ssl == Some(true);
+        match ssl_span {
+            Some(span)
+                if ssl
+                    && settings.ssl_check_cert != Some(false)
+                    && settings.ssl_cert_hostname.is_none() =>
+            {
+                self.errors.push(
+                    ParseError::new(
+                        ErrorCode::IneffectiveProperty,
+                        span,
+                        ".ssl = true requires .ssl_cert_hostname to check the certificate",
+                    )
+                    .with_severity(Severity::Warning)
+                    .with_note("set .ssl_check_cert = never to skip the check"),
+                )
+            }
+            _ => {}
+        }
+        if !ssl {
+            for (key, span) in hostname_spans {
+                self.errors.push(
+                    ParseError::new(
+                        ErrorCode::IneffectiveProperty,
+                        span,
+                        format!(".{} has no effect without .ssl = true", key),
+                    )
+                    .with_severity(Severity::Warning),
+                );
+            }
+        }
+        settings
+    }
+
+    fn varnish(&mut self, settings: &mut BackendConfig, key: &str, value: &ObjectValue) {
+        match key {
+            "path" => settings.path = self.string(key, value),
+            "via" => settings.via = self.ident(key, value, "the name of a backend"),
+            "proxy_header" => {
+                settings.proxy_header = self
+                    .integer(key, value)
+                    .filter(|version| matches!(version, 1 | 2))
+                    .or_else(|| self.invalid(key, "1 or 2"))
+            }
+            "authority" => settings.authority = self.string(key, value),
+            "backend_wait_timeout" => settings.backend_wait_timeout = self.rtime(key, value),
+            "backend_wait_limit" => settings.backend_wait_limit = self.integer(key, value),
+            _ => {
+                let allowed: Vec<&str> = PROPERTIES.into_iter().chain(VARNISH_PROPERTIES).collect();
+                self.unknown(key, &allowed)
+            }
+        }
+    }
+
+    fn probe(&mut self, probe: &Object, spans: &[EntrySpan]) -> ProbeConfig {
+        let mut settings = ProbeConfig::default();
+        let mut threshold_span = None;
+        for ((key, value), entry) in probe.entries.iter().zip(spans) {
+            self.span = entry.span;
+            let key = key.as_str();
+            match key {
+                "dummy" => settings.dummy = self.bool(key, value),
+                "request" => settings.request = self.lines(key, value),
+                "url" => settings.url = self.string(key, value),
+                "expected_response" => settings.expected_response = self.integer(key, value),
+                "interval" => settings.interval = self.rtime(key, value),
+                "timeout" => settings.timeout = self.rtime(key, value),
+                "window" => settings.window = self.integer(key, value),
+                "initial" => settings.initial = self.integer(key, value),
+                "threshold" => {
+                    settings.threshold = self.integer(key, value);
+                    threshold_span = Some(entry.span);
+                }
+                _ => self.unknown(key, &PROBE_PROPERTIES),
+            }
+        }
+        if let (Some(span), Some(threshold), Some(window)) =
+            (threshold_span, settings.threshold, settings.window)
+        {
+            if threshold > window {
+                self.errors.push(ParseError::new(
+                    ErrorCode::InvalidPropertyValue,
+                    span,
+                    format!(
+                        ".threshold must be at most .window ({}), since only that many checks are kept",
+                        window
+                    ),
+                ));
+            }
+        }
+        settings
+    }
+
+    fn string(&mut self, key: &str, value: &ObjectValue) -> Option<String> {
+        match value {
+            ObjectValue::Literal(Literal::String(s)) => {
+                Some(s.tokens.iter().map(|t| t.value.as_str()).collect())
+            }
+            _ => self.invalid(key, "a string"),
+        }
+    }
+
+    /// `"HEAD / HTTP/1.1" "Host: example.com"`
+    fn lines(&mut self, key: &str, value: &ObjectValue) -> Option<Vec<String>> {
+        match value {
+            ObjectValue::Literal(Literal::String(s)) => {
+                Some(s.tokens.iter().map(|t| t.value.clone()).collect())
+            }
+            _ => self.invalid(key, "a string"),
+        }
+    }
+
+    /// `"443"`, which Fastly takes as a string
+    fn port(&mut self, key: &str, value: &ObjectValue) -> Option<u16> {
+        self.string(key, value)?
+            .parse()
+            .ok()
+            .filter(|&port| port != 0)
+            .or_else(|| self.invalid(key, "a port number from 1 to 65535"))
+    }
+
+    fn bool(&mut self, key: &str, value: &ObjectValue) -> Option<bool> {
+        match value {
+            ObjectValue::Literal(Literal::Bool(b)) => Some(b.value),
+            _ => self.invalid(key, "true or false"),
+        }
+    }
+
+    fn integer(&mut self, key: &str, value: &ObjectValue) -> Option<i64> {
+        match value {
+            ObjectValue::Literal(Literal::Integer(i)) => Some(i.value),
+            _ => self.invalid(key, "an integer"),
+        }
+    }
+
+    fn rtime(&mut self, key: &str, value: &ObjectValue) -> Option<RelativeTime> {
+        match value {
+            ObjectValue::Literal(Literal::RTime(t)) => Some(t.value),
+            _ => self.invalid(key, "a duration such as 1s"),
+        }
+    }
+
+    fn ident(&mut self, key: &str, value: &ObjectValue, expected: &str) -> Option<Ident> {
+        match value {
+            ObjectValue::Ident(ident) => Some(ident.clone()),
+            _ => self.invalid(key, expected),
+        }
+    }
+
+    /// `always` or `never`
+    fn check_cert(&mut self, key: &str, value: &ObjectValue) -> Option<bool> {
+        match value {
+            ObjectValue::Ident(ident) if ident == "always" => Some(true),
+            ObjectValue::Ident(ident) if ident == "never" => Some(false),
+            _ => self.invalid(key, "`always` or `never`"),
+        }
+    }
+
+    fn invalid<T>(&mut self, key: &str, expected: &str) -> Option<T> {
+        self.errors.push(ParseError::new(
+            ErrorCode::InvalidPropertyValue,
+            self.span,
+            format!(".{} must be {}", key, expected),
+        ));
+        None
+    }
+
+    fn unknown(&mut self, key: &str, allowed: &[&str]) {
+        let mut error = ParseError::new(
+            ErrorCode::UnknownProperty,
+            self.span,
+            format!("Property .{} is not allowed here", key),
+        )
+        .with_expected(allowed.iter().map(|key| format!(".{}", key)));
+        if let Some(similar) = allowed.iter().find(|k| edit_distance(k, key) <= 2) {
+            error = error.with_note(format!("did you mean .{}?", similar));
+        }
+        self.errors.push(error);
+    }
+}
+
+/// Levenshtein distance, to suggest the property a misspelled one was meant to be.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Checks that the probes and backends named by Varnish backends are declared.
+pub fn check_references(vcl: &Vcl) -> Vec<ParseError> {
+    let declared = |name: &Ident, probe: bool| {
+        vcl.declarations.iter().any(|d| match d {
+            Declaration::Probe(p) if probe => p.name.name == name.name,
+            Declaration::Backend(b) if !probe => b.name.name == name.name,
+            _ => false,
+        })
+    };
+    let mut errors = vec![];
+    for declaration in &vcl.declarations {
+        let Declaration::Backend(backend) = declaration else {
+            continue;
+        };
+        let references = [
+            (&backend.settings.probe_name, true, "Probe"),
+            (&backend.settings.via, false, "Backend"),
+        ];
+        for (name, probe, kind) in references {
+            match name {
+                Some(name) if !declared(name, probe) => errors.push(ParseError::new(
+                    ErrorCode::UndefinedReference,
+                    name.span,
+                    format!("{} `{}` is not declared", kind, name),
+                )),
+                _ => {}
+            }
+        }
+    }
+    errors
+}
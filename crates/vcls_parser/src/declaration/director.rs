@@ -0,0 +1,245 @@
+use vcls_ast::{DirectorDeclaration, DirectorType, Ident, Literal, Object, ObjectValue, Span};
+
+use crate::{
+    cst::SyntaxKind,
+    error::{ErrorCode, ParseError},
+    lexer::TokenKind,
+    literal::object::parse_entry,
+    parser::{Parser, SyntaxResult},
+};
+
+pub fn parse(p: &mut Parser) -> SyntaxResult<DirectorDeclaration> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword("director")?;
+    let name = p.expect_ident()?;
+    let typ_name = p.expect_ident()?;
+    let typ = DirectorType::from_keyword(typ_name.as_str());
+    p.skip_trivia();
+    let body_start = p.offset();
+    let (config, directions) = p.node(SyntaxKind::Block, parse_body)?;
+    let body_span = Span(body_start, p.offset());
+    let span = Span(start, p.offset());
+
+    match Properties::of(&typ) {
+        Some(properties) => {
+            let mut errors = validate(&config, properties.config, &[], span);
+            for direction in &directions {
+                errors.extend(validate(
+                    &direction.entries,
+                    properties.direction,
+                    properties.required,
+                    direction.span,
+                ));
+            }
+            p.errors.extend(errors);
+        }
+        None => p.errors.push(
+            ParseError::new(
+                ErrorCode::UnknownDirectorType,
+                typ_name.span,
+                format!("Unknown director type: {}", typ_name),
+            )
+            .with_expected(["random", "fallback", "hash", "client", "chash"]),
+        ),
+    }
+
+    Ok(DirectorDeclaration {
+        name,
+        typ,
+        config: if config.is_empty() {
+            None
+        } else {
+            Some(Object {
+                entries: config.into_iter().map(|e| (e.key, e.value)).collect(),
+                span: body_span,
+            })
+        },
+        directions: directions
+            .into_iter()
+            .map(|d| Object {
+                entries: d.entries.into_iter().map(|e| (e.key, e.value)).collect(),
+                span: d.span,
+            })
+            .collect(),
+        span,
+    })
+}
+
+struct Entry {
+    key: Ident,
+    value: ObjectValue,
+    span: Span,
+}
+
+struct Direction {
+    entries: Vec<Entry>,
+    span: Span,
+}
+
+/// Director properties (`.quorum = 50%;`) mixed with backend entries (`{ .backend = F; }`)
+fn parse_body(p: &mut Parser) -> SyntaxResult<(Vec<Entry>, Vec<Direction>)> {
+    p.expect(TokenKind::LBrace, "{")?;
+    let mut config = vec![];
+    let mut directions = vec![];
+    loop {
+        p.skip_trivia();
+        match p.peek().map(|t| t.kind) {
+            Some(TokenKind::RBrace) => {
+                p.bump();
+                return Ok((config, directions));
+            }
+            Some(TokenKind::Dot) => config.push(parse_director_entry(p)?),
+            Some(TokenKind::LBrace) => {
+                directions.push(p.node(SyntaxKind::Object, parse_direction)?)
+            }
+            _ => return Err(p.unexpected(&["Property", "{", "}"])),
+        }
+    }
+}
+
+fn parse_director_entry(p: &mut Parser) -> SyntaxResult<Entry> {
+    p.skip_trivia();
+    let start = p.offset();
+    let (key, value) = parse_entry(p)?;
+    Ok(Entry {
+        key,
+        value,
+        span: Span(start, p.offset()),
+    })
+}
+
+fn parse_direction(p: &mut Parser) -> SyntaxResult<Direction> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect(TokenKind::LBrace, "{")?;
+    let mut entries = vec![];
+    loop {
+        p.skip_trivia();
+        if p.eat(TokenKind::RBrace).is_some() {
+            break;
+        }
+        if !p.at(TokenKind::Dot) {
+            return Err(p.unexpected(&["Property", "}"]));
+        }
+        entries.push(parse_director_entry(p)?);
+    }
+    Ok(Direction {
+        entries,
+        span: Span(start, p.offset()),
+    })
+}
+
+#[derive(Clone, Copy)]
+enum ValueKind {
+    Backend,
+    Integer,
+    Percentage,
+    String,
+    /// `object` or `client`
+    HashKey,
+}
+
+impl ValueKind {
+    fn accepts(&self, value: &ObjectValue) -> bool {
+        match (self, value) {
+            (Self::Backend, ObjectValue::Ident(_)) => true,
+            (Self::Integer, ObjectValue::Literal(Literal::Integer(_))) => true,
+            (Self::Percentage, ObjectValue::Literal(Literal::Percentage(_))) => true,
+            (Self::String, ObjectValue::Literal(Literal::String(_))) => true,
+            (Self::HashKey, ObjectValue::Ident(key)) => key == "object" || key == "client",
+            _ => false,
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            Self::Backend => "a backend name",
+            Self::Integer => "an integer",
+            Self::Percentage => "a percentage",
+            Self::String => "a string",
+            Self::HashKey => "`object` or `client`",
+        }
+    }
+}
+
+/// Properties allowed for each director type.
+/// https://developer.fastly.com/reference/vcl/declarations/director/
+struct Properties {
+    config: &'static [(&'static str, ValueKind)],
+    direction: &'static [(&'static str, ValueKind)],
+    required: &'static [&'static str],
+}
+
+impl Properties {
+    fn of(typ: &DirectorType) -> Option<&'static Properties> {
+        const QUORUM: (&str, ValueKind) = ("quorum", ValueKind::Percentage);
+        const BACKEND: (&str, ValueKind) = ("backend", ValueKind::Backend);
+        const WEIGHT: (&str, ValueKind) = ("weight", ValueKind::Integer);
+        match typ {
+            DirectorType::Random => Some(&Properties {
+                config: &[QUORUM, ("retries", ValueKind::Integer)],
+                direction: &[BACKEND, WEIGHT],
+                required: &["backend", "weight"],
+            }),
+            DirectorType::Fallback => Some(&Properties {
+                config: &[],
+                direction: &[BACKEND],
+                required: &["backend"],
+            }),
+            DirectorType::Content | DirectorType::Client => Some(&Properties {
+                config: &[QUORUM],
+                direction: &[BACKEND, WEIGHT],
+                required: &["backend", "weight"],
+            }),
+            DirectorType::ConsistentHashing => Some(&Properties {
+                config: &[
+                    QUORUM,
+                    ("key", ValueKind::HashKey),
+                    ("seed", ValueKind::Integer),
+                    ("vnodes_per_node", ValueKind::Integer),
+                ],
+                direction: &[BACKEND, ("id", ValueKind::String)],
+                required: &["backend", "id"],
+            }),
+            DirectorType::Unknown(_) => None,
+        }
+    }
+}
+
+fn validate(
+    entries: &[Entry],
+    allowed: &[(&str, ValueKind)],
+    required: &[&str],
+    span: Span,
+) -> Vec<ParseError> {
+    let mut errors = vec![];
+    for entry in entries {
+        match allowed.iter().find(|(key, _)| entry.key == *key) {
+            Some((_, kind)) if !kind.accepts(&entry.value) => errors.push(ParseError::new(
+                ErrorCode::InvalidPropertyValue,
+                entry.span,
+                format!(".{} must be {}", entry.key, kind.describe()),
+            )),
+            Some(_) => {}
+            None => errors.push(
+                ParseError::new(
+                    ErrorCode::UnknownProperty,
+                    entry.span,
+                    format!("Property .{} is not allowed here", entry.key),
+                )
+                .with_expected(allowed.iter().map(|(key, _)| format!(".{}", key))),
+            ),
+        }
+    }
+    for key in required {
+        if !entries.iter().any(|e| e.key == *key) {
+            errors.push(ParseError::new(
+                ErrorCode::MissingProperty,
+                span,
+                format!("Missing required property .{}", key),
+            ));
+        }
+    }
+    errors
+}
@@ -1,17 +1,18 @@
-use pest::iterators::Pair;
-use vcls_ast::ImportDeclaration;
+use vcls_ast::{ImportDeclaration, Span};
 
-use crate::{error::ParseError, utils::convert_span, ParseResult, Rule};
+use crate::{
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<ImportDeclaration> {
-    let span = convert_span(pair.as_span());
-    let mut inner = pair.into_inner();
-    let ident = inner
-        .find(|p| p.as_rule() == Rule::Ident)
-        .ok_or(vec![ParseError {
-            message: "Import declaration must have a identifier".to_string(),
-        }])?
-        .as_str()
-        .to_string();
-    Ok(ImportDeclaration { ident, span })
+pub fn parse(p: &mut Parser) -> SyntaxResult<ImportDeclaration> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword("import")?;
+    let ident = p.expect_ident()?;
+    p.expect(TokenKind::Semicolon, ";")?;
+    Ok(ImportDeclaration {
+        ident,
+        span: Span(start, p.offset()),
+    })
 }
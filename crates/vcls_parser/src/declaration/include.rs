@@ -1,21 +1,19 @@
-use pest::iterators::Pair;
-use vcls_ast::IncludeDeclaration;
+use vcls_ast::{IncludeDeclaration, Span};
 
 use crate::{
-    error::ParseError, literal::string::handle_quoted_string, utils::convert_span, ParseResult,
-    Rule,
+    lexer::TokenKind,
+    literal::string::parse_quoted,
+    parser::{Parser, SyntaxResult},
 };
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<IncludeDeclaration> {
-    let span = convert_span(pair.as_span());
-    let mut inner = pair.into_inner();
-    let quoted_string = inner
-        .find(|p| p.as_rule() == Rule::QuotedString)
-        .ok_or(vec![ParseError {
-            message: "Include declaration must have a path".to_string(),
-        }])?;
+pub fn parse(p: &mut Parser) -> SyntaxResult<IncludeDeclaration> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword("include")?;
+    let path = parse_quoted(p)?;
+    p.expect(TokenKind::Semicolon, ";")?;
     Ok(IncludeDeclaration {
-        path: handle_quoted_string(quoted_string),
-        span,
+        path,
+        span: Span(start, p.offset()),
     })
 }
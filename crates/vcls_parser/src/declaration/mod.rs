@@ -1,37 +1,72 @@
-use pest::iterators::Pair;
 use vcls_ast::Declaration;
 
-use crate::{ParseResult, Rule};
+use crate::{
+    cst::SyntaxKind,
+    parser::{Parser, SyntaxResult},
+};
 
 pub mod acl;
 pub mod backend;
+pub mod director;
 pub mod import;
 pub mod include;
 pub mod penaltybox;
+pub mod probe;
 pub mod ratecounter;
 pub mod sub;
 pub mod table;
+pub mod version;
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<Declaration> {
-    debug_assert!(pair.as_rule() == Rule::Declaration);
-    let inner = pair.into_inner();
-    for pair in inner {
-        match pair.as_rule() {
-            Rule::IncludeDeclaration => return Ok(Declaration::Include(include::handle(pair)?)),
-            Rule::ImportDeclaration => return Ok(Declaration::Import(import::handle(pair)?)),
-            Rule::AclDeclaration => return Ok(Declaration::Acl(acl::handle(pair)?)),
-            Rule::PenaltyboxDeclaration => {
-                return Ok(Declaration::PenaltyBox(penaltybox::handle(pair)?))
-            }
-            Rule::RateCounterDeclaration => {
-                return Ok(Declaration::RateCounter(ratecounter::handle(pair)?))
-            }
-            Rule::TableDeclaration => return Ok(Declaration::Table(table::handle(pair)?)),
-            Rule::BackendDeclaration => return Ok(Declaration::Backend(backend::handle(pair)?)),
-            Rule::SubDeclaration => return Ok(Declaration::Subroutine(sub::handle(pair)?)),
-            Rule::COMMENT => {}
-            _ => unreachable!("Unexpected rule: {:?}", pair.as_rule()),
-        }
+pub const KEYWORDS: [&str; 11] = [
+    "include",
+    "import",
+    "sub",
+    "acl",
+    "backend",
+    "director",
+    "penaltybox",
+    "ratecounter",
+    "table",
+    "vcl",
+    "probe",
+];
+
+pub fn parse(p: &mut Parser) -> SyntaxResult<Declaration> {
+    p.skip_trivia();
+    match p.keyword() {
+        Some("include") => p
+            .node(SyntaxKind::IncludeDeclaration, include::parse)
+            .map(Declaration::Include),
+        Some("import") => p
+            .node(SyntaxKind::ImportDeclaration, import::parse)
+            .map(Declaration::Import),
+        Some("sub") => p
+            .node(SyntaxKind::SubroutineDeclaration, sub::parse)
+            .map(Declaration::Subroutine),
+        Some("acl") => p
+            .node(SyntaxKind::AclDeclaration, acl::parse)
+            .map(Declaration::Acl),
+        Some("backend") => p
+            .node(SyntaxKind::BackendDeclaration, backend::parse)
+            .map(Declaration::Backend),
+        Some("director") => p
+            .node(SyntaxKind::DirectorDeclaration, director::parse)
+            .map(Declaration::Director),
+        Some("penaltybox") => p
+            .node(SyntaxKind::PenaltyBoxDeclaration, penaltybox::parse)
+            .map(Declaration::PenaltyBox),
+        Some("ratecounter") => p
+            .node(SyntaxKind::RateCounterDeclaration, ratecounter::parse)
+            .map(Declaration::RateCounter),
+        Some("table") => p
+            .node(SyntaxKind::TableDeclaration, table::parse)
+            .map(Declaration::Table),
+        Some("vcl") => p
+            .node(SyntaxKind::VersionDeclaration, version::parse)
+            .map(Declaration::Version),
+        Some("probe") => p
+            .node(SyntaxKind::ProbeDeclaration, probe::parse)
+            .map(Declaration::Probe),
+        _ => Err(p.unexpected(&["Declaration"])),
     }
-    unreachable!()
 }
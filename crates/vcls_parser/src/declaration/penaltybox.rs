@@ -1,17 +1,22 @@
-use pest::iterators::Pair;
-use vcls_ast::PenaltyBoxDeclaration;
+use vcls_ast::{PenaltyBoxDeclaration, Span};
 
-use crate::{error::ParseError, utils::convert_span, ParseResult, Rule};
+use crate::{
+    cst::SyntaxKind,
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<PenaltyBoxDeclaration> {
-    let span = convert_span(pair.as_span());
-    let mut inner = pair.into_inner();
-    let name = inner
-        .find(|p| p.as_rule() == Rule::Ident)
-        .ok_or(vec![ParseError {
-            message: "Import declaration must have a identifier".to_string(),
-        }])?
-        .as_str()
-        .to_string();
-    Ok(PenaltyBoxDeclaration { name, span })
+pub fn parse(p: &mut Parser) -> SyntaxResult<PenaltyBoxDeclaration> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword("penaltybox")?;
+    let name = p.expect_ident()?;
+    p.node(SyntaxKind::Block, |p| {
+        p.expect(TokenKind::LBrace, "{")?;
+        p.expect(TokenKind::RBrace, "}")
+    })?;
+    Ok(PenaltyBoxDeclaration {
+        name,
+        span: Span(start, p.offset()),
+    })
 }
@@ -0,0 +1,20 @@
+use vcls_ast::{ProbeDeclaration, Span};
+
+use crate::{
+    literal::object,
+    parser::{Dialect, Parser, SyntaxResult},
+};
+
+pub fn parse(p: &mut Parser) -> SyntaxResult<ProbeDeclaration> {
+    p.skip_trivia();
+    let start = p.offset();
+    let keyword = p.expect_keyword("probe")?;
+    let name = p.expect_ident()?;
+    let config = object::parse(p)?;
+    p.require_dialect(Dialect::Varnish, keyword.span, "`probe` declaration");
+    Ok(ProbeDeclaration {
+        name,
+        config,
+        span: Span(start, p.offset()),
+    })
+}
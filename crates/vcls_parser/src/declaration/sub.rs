@@ -1,58 +1,33 @@
-use pest::iterators::Pair;
-use vcls_ast::{Statement, SubroutineDeclaration, Type};
+use vcls_ast::{Span, SubroutineDeclaration, Type};
 
-use crate::{error::ParseError, statement, utils::convert_span, ParseResult, Rule};
+use crate::{
+    parser::{Parser, SyntaxResult},
+    recovery, statement,
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<SubroutineDeclaration> {
-    debug_assert!(pair.as_rule() == Rule::SubDeclaration);
-    let mut inner = pair.into_inner();
-    let name = inner
-        .find(|p| p.as_rule() == Rule::Ident)
-        .unwrap()
-        .as_str()
-        .to_string();
-    let mut typ = Type::Void;
-    for pair in inner {
-        let span = convert_span(pair.as_span());
-        match pair.as_rule() {
-            Rule::Type => {
-                typ = Type::from_keyword(pair.as_str());
-            }
-            Rule::SubBody => {
-                return Ok(SubroutineDeclaration {
-                    name,
-                    return_type: typ,
-                    body: handle_sub_body(pair)?,
-                    span,
-                })
-            }
-            Rule::COMMENT => {}
-            _ => unreachable!("Unexpected rule: {:?}", pair.as_rule()),
-        }
-    }
-    Err(vec![ParseError {
-        message: "Subroutine declaration must have a body".to_string(),
-    }])
-}
-
-fn handle_sub_body(pair: Pair<Rule>) -> ParseResult<Vec<Statement>> {
-    debug_assert!(pair.as_rule() == Rule::SubBody);
-    let inner = pair.into_inner();
-    let mut statements = vec![];
-    let mut errors = vec![];
-    for pair in inner {
-        match pair.as_rule() {
-            Rule::Statement => match statement::handle(pair) {
-                Ok(s) => statements.push(s),
-                Err(e) => errors.extend(e),
-            },
-            Rule::COMMENT => {}
-            _ => unreachable!("Unexpected rule: {:?}", pair.as_rule()),
-        }
-    }
-    if errors.is_empty() {
-        Ok(statements)
+pub fn parse(p: &mut Parser) -> SyntaxResult<SubroutineDeclaration> {
+    p.expect_keyword("sub")?;
+    let name = p.expect_ident()?;
+    p.skip_trivia();
+    let return_type = if p.at_type() {
+        p.expect_type()?
+    } else {
+        Type::Void
+    };
+    p.return_type = return_type.clone();
+    p.skip_trivia();
+    let start = p.offset();
+    let body = if p.recovering {
+        recovery::parse_sub_body(p)?
     } else {
-        Err(errors)
-    }
+        statement::parse_block(p)?
+    };
+    let errors = statement::goto::validate(&body);
+    p.errors.extend(errors);
+    Ok(SubroutineDeclaration {
+        name,
+        return_type,
+        body,
+        span: Span(start, p.offset()),
+    })
 }
@@ -1,101 +1,241 @@
-use pest::iterators::Pair;
-use vcls_ast::{TableDeclaration, TableEntry, TableValue, Type, Variable};
+use std::{collections::HashMap, net::IpAddr};
+
+use vcls_ast::{
+    Declaration, Literal, Span, TableDeclaration, TableEntry, TableValue, Type, Variable, Vcl,
+};
 
 use crate::{
-    error::ParseError,
-    literal,
-    literal::string,
-    utils::{convert_span, skip_comments},
-    ParseResult, Rule,
+    cst::SyntaxKind,
+    declaration::backend,
+    error::{ErrorCode, ParseError},
+    lexer::TokenKind,
+    literal::{self, bool, string},
+    parser::{Parser, SyntaxResult},
 };
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<TableDeclaration> {
-    debug_assert!(pair.as_rule() == Rule::TableDeclaration);
-    let span = convert_span(pair.as_span());
-    let mut inner = skip_comments(pair.into_inner());
-    let name = inner
-        .next()
-        .ok_or(vec![ParseError {
-            message: "Table must have a name".to_string(),
-        }])?
-        .as_str()
-        .to_string();
-    let next = inner.next().ok_or(vec![ParseError {
-        message: "Table must have a body".to_string(),
-    }])?;
-    let typ = if next.as_rule() == Rule::TableType {
-        Type::from_keyword(next.as_str())
+pub fn parse(p: &mut Parser) -> SyntaxResult<TableDeclaration> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword("table")?;
+    let name = p.expect_ident()?;
+    p.skip_trivia();
+    let typ = if p.at_type() {
+        p.expect_type()?
     } else {
         Type::String
     };
-    match handle_table_body(next) {
-        Ok(entries) => Ok(TableDeclaration {
-            name,
-            typ,
-            entries,
-            span,
-        }),
-        Err(e) => Err(e),
-    }
+    let entries = p.node(SyntaxKind::Block, parse_body)?;
+    Ok(TableDeclaration {
+        name,
+        typ,
+        entries,
+        span: Span(start, p.offset()),
+    })
 }
 
-fn handle_table_body(pair: Pair<Rule>) -> ParseResult<Vec<TableEntry>> {
-    debug_assert!(pair.as_rule() == Rule::TableBody);
+/// `{ "key": value, ... }` with an optional trailing comma
+fn parse_body(p: &mut Parser) -> SyntaxResult<Vec<TableEntry>> {
+    p.expect(TokenKind::LBrace, "{")?;
     let mut entries = vec![];
-    let mut errors = vec![];
-    for entry in pair.into_inner() {
-        match entry.as_rule() {
-            Rule::TableEntry => match handle_table_entry(entry) {
-                Ok(e) => entries.push(e),
-                Err(e) => errors.extend(e),
-            },
-            Rule::COMMENT => {}
-            _ => unreachable!("Unexpected token: {:?}", entry.as_rule()),
+    loop {
+        p.skip_trivia();
+        if p.eat(TokenKind::RBrace).is_some() {
+            return Ok(entries);
+        }
+        if !string::at_string(p) {
+            return Err(p.unexpected(&["String", "}"]));
+        }
+        entries.push(p.node(SyntaxKind::TableEntry, parse_entry)?);
+        p.skip_trivia();
+        if p.eat(TokenKind::Comma).is_none() {
+            return match p.eat(TokenKind::RBrace) {
+                Some(_) => Ok(entries),
+                None => Err(p.unexpected(&[",", "}"])),
+            };
         }
-    }
-    if errors.is_empty() {
-        Ok(entries)
-    } else {
-        Err(errors)
     }
 }
 
-fn handle_table_entry(pair: Pair<Rule>) -> ParseResult<TableEntry> {
-    debug_assert!(pair.as_rule() == Rule::TableEntry);
-    let span = convert_span(pair.as_span());
-    let mut inner = pair.into_inner();
-    let key = string::handle(
-        inner
-            .find(|p| p.as_rule() == Rule::TableKey)
-            .ok_or(vec![ParseError {
-                message: "Table entry must have a key".to_string(),
-            }])?
-            .into_inner()
-            .next()
-            .unwrap(),
-    )?;
-    let value = inner
-        .find(|p| p.as_rule() == Rule::TableValue)
-        .ok_or(vec![ParseError {
-            message: "Table entry must have a value".to_string(),
-        }])?
-        .into_inner()
-        .next()
-        .unwrap();
-    Ok(TableEntry {
-        key,
-        value: match value.as_rule() {
-            Rule::String | Rule::RTime | Rule::Number | Rule::Bool => {
-                TableValue::Literal(literal::handle(value)?)
-            }
-            Rule::Ident => TableValue::Ident(Variable {
-                name: value.as_str().to_string(),
+fn parse_entry(p: &mut Parser) -> SyntaxResult<TableEntry> {
+    p.skip_trivia();
+    let start = p.offset();
+    let key = string::parse(p)?;
+    p.expect(TokenKind::Colon, ":")?;
+    p.skip_trivia();
+    let value = match p.peek() {
+        Some(token) if token.kind == TokenKind::Ident && !bool::at_bool(p) => {
+            let ident = p.expect_ident()?;
+            TableValue::Ident(Variable {
+                name: ident.name,
                 properties: vec![],
                 sub_field: None,
-                span: convert_span(value.as_span()),
-            }),
-            _ => unreachable!("Unexpected token: {:?}", value.as_rule()),
-        },
-        span,
+                span: ident.span,
+            })
+        }
+        _ => TableValue::Literal(literal::parse(p)?),
+    };
+    Ok(TableEntry {
+        key,
+        value,
+        span: Span(start, p.offset()),
     })
 }
+
+/// Error codes of `check`, which depends on the whole source rather than on a single declaration.
+pub const CHECK_CODES: [ErrorCode; 3] = [
+    ErrorCode::InvalidTableValue,
+    ErrorCode::DuplicateTableKey,
+    ErrorCode::UndefinedReference,
+];
+
+/// Checks the values of every table against its type, including that BACKEND and ACL values
+/// name a declared backend or ACL, and that no key is repeated, as well as the references of
+/// backends.
+///
+/// References are not checked in sources with `include`, which may declare them elsewhere.
+pub fn check(vcl: &Vcl) -> Vec<ParseError> {
+    let resolve = !vcl
+        .declarations
+        .iter()
+        .any(|d| matches!(d, Declaration::Include(_)));
+    let mut errors = check_tables(vcl, resolve);
+    if resolve {
+        errors.extend(backend::check_references(vcl));
+    }
+    errors
+}
+
+/// `check` without resolving references, for declarations parsed out of their file.
+pub fn check_unresolved(vcl: &Vcl) -> Vec<ParseError> {
+    check_tables(vcl, false)
+}
+
+fn check_tables(vcl: &Vcl, resolve: bool) -> Vec<ParseError> {
+    let mut errors = vec![];
+    for declaration in &vcl.declarations {
+        let Declaration::Table(table) = declaration else {
+            continue;
+        };
+        let mut keys: HashMap<String, Span> = HashMap::new();
+        for entry in &table.entries {
+            let key: String = entry.key.tokens.iter().map(|t| t.value.as_str()).collect();
+            if let Some(first) = keys.get(&key) {
+                errors.push(
+                    ParseError::new(
+                        ErrorCode::DuplicateTableKey,
+                        entry.span,
+                        format!("Duplicate key `{}` in table `{}`", key, table.name),
+                    )
+                    .with_related(*first, "first defined here"),
+                );
+            } else {
+                keys.insert(key, entry.span);
+            }
+            errors.extend(check_value(vcl, table, entry, resolve));
+        }
+    }
+    errors
+}
+
+fn check_value(
+    vcl: &Vcl,
+    table: &TableDeclaration,
+    entry: &TableEntry,
+    resolve: bool,
+) -> Option<ParseError> {
+    let found = match (&table.typ, &entry.value) {
+        (Type::String, TableValue::Literal(Literal::String(_)))
+        | (Type::Integer, TableValue::Literal(Literal::Integer(_)))
+        | (Type::Float, TableValue::Literal(Literal::Float(_) | Literal::Integer(_)))
+        | (Type::Bool, TableValue::Literal(Literal::Bool(_)))
+        | (Type::RTime, TableValue::Literal(Literal::RTime(_))) => return None,
+        (Type::IP, TableValue::Literal(Literal::String(s))) => {
+            let value: String = s.tokens.iter().map(|t| t.value.as_str()).collect();
+            if value.parse::<IpAddr>().is_ok() {
+                return None;
+            }
+            return Some(ParseError::new(
+                ErrorCode::InvalidTableValue,
+                entry.span,
+                format!("Invalid IP address `{}` in table `{}`", value, table.name),
+            ));
+        }
+        (Type::Backend | Type::Acl, TableValue::Ident(ident)) => {
+            let declared = vcl.declarations.iter().any(|d| match (&table.typ, d) {
+                (Type::Backend, Declaration::Backend(b)) => b.name == ident.name.as_str(),
+                (Type::Backend, Declaration::Director(d)) => d.name == ident.name.as_str(),
+                (Type::Acl, Declaration::Acl(a)) => a.name == ident.name.as_str(),
+                _ => false,
+            });
+            if declared || !resolve {
+                return None;
+            }
+            return Some(ParseError::new(
+                ErrorCode::UndefinedReference,
+                entry.span,
+                format!(
+                    "{} `{}` is not declared",
+                    if table.typ == Type::Backend {
+                        "Backend"
+                    } else {
+                        "ACL"
+                    },
+                    ident.name
+                ),
+            ));
+        }
+        (
+            Type::String
+            | Type::Integer
+            | Type::Float
+            | Type::Bool
+            | Type::RTime
+            | Type::IP
+            | Type::Backend
+            | Type::Acl,
+            value,
+        ) => value_type(value),
+        // Other types cannot be used for tables and are not checked.
+        _ => return None,
+    };
+    Some(ParseError::new(
+        ErrorCode::InvalidTableValue,
+        entry.span,
+        format!(
+            "Table `{}` has {} values, found {}",
+            table.name,
+            type_name(&table.typ),
+            found
+        ),
+    ))
+}
+
+fn type_name(typ: &Type) -> &str {
+    match typ {
+        Type::Acl => "ACL",
+        Type::Backend => "BACKEND",
+        Type::Bool => "BOOL",
+        Type::Float => "FLOAT",
+        Type::ID => "ID",
+        Type::Integer => "INTEGER",
+        Type::IP => "IP",
+        Type::RTime => "RTIME",
+        Type::String => "STRING",
+        Type::Time => "TIME",
+        Type::Void => "VOID",
+        Type::Unknown(name) => name,
+    }
+}
+
+fn value_type(value: &TableValue) -> &'static str {
+    match value {
+        TableValue::Ident(_) => "an identifier",
+        TableValue::Literal(Literal::String(_)) => "STRING",
+        TableValue::Literal(Literal::Integer(_)) => "INTEGER",
+        TableValue::Literal(Literal::Float(_)) => "FLOAT",
+        TableValue::Literal(Literal::Bool(_)) => "BOOL",
+        TableValue::Literal(Literal::RTime(_)) => "RTIME",
+        TableValue::Literal(Literal::Percentage(_)) => "a percentage",
+        TableValue::Literal(Literal::Object(_)) => "an object",
+    }
+}
@@ -0,0 +1,20 @@
+use vcls_ast::{Span, VersionDeclaration};
+
+use crate::{
+    lexer::TokenKind,
+    parser::{Dialect, Parser, SyntaxResult},
+};
+
+pub fn parse(p: &mut Parser) -> SyntaxResult<VersionDeclaration> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword("vcl")?;
+    let version = p.expect(TokenKind::Number, "Version")?;
+    p.expect(TokenKind::Semicolon, ";")?;
+    let span = Span(start, p.offset());
+    p.require_dialect(Dialect::Varnish, span, "`vcl` version declaration");
+    Ok(VersionDeclaration {
+        version: p.text(version).to_string(),
+        span,
+    })
+}
@@ -1,4 +1,181 @@
-#[derive(Debug, PartialEq)]
+use std::fmt;
+
+use vcls_ast::Span;
+
+/// Stable identifier of a diagnostic.
+/// The string form (`E0001`, ...) is part of the public contract and never reused.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ErrorCode {
+    /// The source does not match the grammar.
+    Syntax,
+    /// A construct lacks a required part (name, body, value, ...).
+    Missing,
+    /// A number literal cannot be represented.
+    InvalidNumber,
+    /// An RTIME literal cannot be represented.
+    InvalidRTime,
+    /// A boolean literal is neither `true` nor `false`.
+    InvalidBool,
+    /// An assignment operator is not supported by `set`.
+    InvalidSetOperator,
+    /// An ACL entry is malformed.
+    InvalidAclEntry,
+    /// An object has a property which is not allowed in its context.
+    UnknownProperty,
+    /// An object property has a value of the wrong type.
+    InvalidPropertyValue,
+    /// An object lacks a required property.
+    MissingProperty,
+    /// A director has an unsupported type.
+    UnknownDirectorType,
+    /// A percent escape in a string is malformed or encodes an invalid character.
+    InvalidEscape,
+    /// A `return` of a subroutine without a return type has an unsupported action.
+    UnknownReturnAction,
+    /// A `goto` refers to a label which is not in its subroutine.
+    UnknownLabel,
+    /// A `goto` jumps to a label before it.
+    BackwardGoto,
+    /// A label is defined twice in a subroutine.
+    DuplicateLabel,
+    /// A construct of another dialect of VCL.
+    UnsupportedInDialect,
+    /// A table value does not have the type of its table.
+    InvalidTableValue,
+    /// A table has the same key twice.
+    DuplicateTableKey,
+    /// A name does not refer to any declaration.
+    UndefinedReference,
+    /// Constructs are nested deeper than the parser supports.
+    TooDeep,
+    /// An object property has no effect, or lacks another property it relies on.
+    IneffectiveProperty,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Syntax => "E0001",
+            Self::Missing => "E0002",
+            Self::InvalidNumber => "E0003",
+            Self::InvalidRTime => "E0004",
+            Self::InvalidBool => "E0005",
+            Self::InvalidSetOperator => "E0006",
+            Self::InvalidAclEntry => "E0007",
+            Self::UnknownProperty => "E0008",
+            Self::InvalidPropertyValue => "E0009",
+            Self::MissingProperty => "E0010",
+            Self::UnknownDirectorType => "E0011",
+            Self::InvalidEscape => "E0012",
+            Self::UnknownReturnAction => "E0013",
+            Self::UnknownLabel => "E0014",
+            Self::BackwardGoto => "E0015",
+            Self::DuplicateLabel => "E0016",
+            Self::UnsupportedInDialect => "E0017",
+            Self::InvalidTableValue => "E0018",
+            Self::DuplicateTableKey => "E0019",
+            Self::UndefinedReference => "E0020",
+            Self::TooDeep => "E0021",
+            Self::IneffectiveProperty => "E0022",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => f.write_str("error"),
+            Self::Warning => f.write_str("warning"),
+            Self::Info => f.write_str("info"),
+        }
+    }
+}
+
+/// Secondary location attached to a diagnostic (e.g. a previous definition).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Related {
+    pub span: Span,
+    pub message: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct ParseError {
+    pub code: ErrorCode,
+    pub severity: Severity,
     pub message: String,
+    pub span: Span,
+    /// Constructs or tokens which would have been accepted at `span`.
+    pub expected: Vec<String>,
+    pub related: Vec<Related>,
+    pub notes: Vec<String>,
+}
+
+impl ParseError {
+    pub fn new(code: ErrorCode, span: Span, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            severity: Severity::Error,
+            message: message.into(),
+            span,
+            expected: vec![],
+            related: vec![],
+            notes: vec![],
+        }
+    }
+
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    pub fn with_expected<I, S>(mut self, expected: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.expected.extend(expected.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_related(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.related.push(Related {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}]: {} at {}..{}",
+            self.severity, self.code, self.message, self.span.0, self.span.1
+        )?;
+        if !self.expected.is_empty() {
+            write!(f, " (expected {})", self.expected.join(", "))?;
+        }
+        Ok(())
+    }
 }
+
+impl std::error::Error for ParseError {}
@@ -1,151 +1,225 @@
-use std::sync::OnceLock;
+use std::iter::Peekable;
 
-use pest::{
-    iterators::Pair,
-    pratt_parser::{Assoc, Op, PrattParser},
-};
 use vcls_ast::{
-    BinaryExpression, BinaryOperator, Expression, Literal, Span, UnaryExpression, UnaryOperator,
+    BinaryExpression, BinaryOperator, CallExpression, ConcatExpression, Expression, Literal,
+    ParenExpression, Span, UnaryExpression, UnaryOperator,
 };
 
-use crate::{literal, utils::convert_span, variable, ParseResult, Rule};
+use crate::{
+    cst::SyntaxKind,
+    lexer::TokenKind,
+    literal::{self, object, string},
+    parser::{Parser, SyntaxResult},
+    variable,
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<Expression> {
-    debug_assert!(pair.as_rule() == Rule::Expr);
-    static PARSER: OnceLock<PrattParser<Rule>> = OnceLock::new();
-    let pratt = PARSER.get_or_init(|| {
-        PrattParser::new()
-            .op(Op::prefix(Rule::OpNot))
-            .op(Op::infix(Rule::OpAnd, Assoc::Left) | Op::infix(Rule::OpOr, Assoc::Left))
-            .op(Op::infix(Rule::OpEq, Assoc::Left)
-                | Op::infix(Rule::OpNe, Assoc::Left)
-                | Op::infix(Rule::OpLt, Assoc::Left)
-                | Op::infix(Rule::OpGt, Assoc::Left)
-                | Op::infix(Rule::OpLe, Assoc::Left)
-                | Op::infix(Rule::OpGe, Assoc::Left))
-            .op(Op::infix(Rule::OpRegexMatch, Assoc::Left)
-                | Op::infix(Rule::OpRegexNotMatch, Assoc::Left))
-            .op(Op::infix(Rule::OpAdd, Assoc::Left) | Op::infix(Rule::OpSub, Assoc::Left))
-            .op(Op::infix(Rule::OpMul, Assoc::Left) | Op::infix(Rule::OpDiv, Assoc::Left))
-            .op(Op::prefix(Rule::OpMinus))
-    });
-    pratt
-        .map_primary(|p| match p.as_rule() {
-            Rule::Primary => handle_primary(p),
-            _ => unreachable!("Unexpected token: {:?}", p.as_str()),
-        })
-        .map_prefix(|p, rhs| {
-            let span = convert_span(p.as_span());
-            match p.as_rule() {
-                Rule::OpNot => Ok(Expression::Unary(UnaryExpression {
-                    operator: UnaryOperator::Not,
-                    rhs: Box::new(rhs?),
-                    span,
-                })),
-                Rule::OpMinus => Ok(Expression::Unary(UnaryExpression {
-                    operator: UnaryOperator::Neg,
-                    rhs: Box::new(rhs?),
-                    span,
-                })),
-                _ => unreachable!("Unexpected token: {:?}", p.as_str()),
-            }
-        })
-        .map_infix(|lhs, p, rhs| {
-            let span = convert_span(p.as_span());
-            match p.as_rule() {
-                Rule::OpEq => Ok(Expression::Binary(BinaryExpression {
-                    lhs: Box::new(lhs?),
-                    operator: BinaryOperator::Eq,
-                    rhs: Box::new(rhs?),
-                    span,
-                })),
-                Rule::OpNe => Ok(Expression::Binary(BinaryExpression {
-                    lhs: Box::new(lhs?),
-                    operator: BinaryOperator::Ne,
-                    rhs: Box::new(rhs?),
-                    span,
-                })),
-                Rule::OpRegexMatch => Ok(Expression::Binary(BinaryExpression {
-                    lhs: Box::new(lhs?),
-                    operator: BinaryOperator::Tilde,
-                    rhs: Box::new(rhs?),
-                    span,
-                })),
-                Rule::OpRegexNotMatch => Ok(Expression::Binary(BinaryExpression {
-                    lhs: Box::new(lhs?),
-                    operator: BinaryOperator::NotTilde,
-                    rhs: Box::new(rhs?),
-                    span,
-                })),
-                _ => unreachable!("Unexpected token: {:?}", p.as_str()),
-            }
-        })
-        .parse(pair.into_inner())
+/// Precedence follows C, from the loosest binding:
+/// `||`, `&&`, `== != ~ !~`, `< > <= >=`, `+ -`, `* /`, then the prefix operators `!` and `-`.
+/// All binary operators are left-associative.
+///
+/// Operators count towards the nesting depth until the outermost expression ends, since a
+/// chain of them nests as deep in the AST.
+pub fn parse(p: &mut Parser) -> SyntaxResult<Expression> {
+    let outermost = p.expression_depth.is_none();
+    if outermost {
+        p.expression_depth = Some(p.depth());
+    }
+    let result = p.node(SyntaxKind::Expression, parse_binary);
+    if let Some(depth) = p.expression_depth.filter(|_| outermost) {
+        p.ascend_to(depth);
+        p.expression_depth = None;
+    }
+    result
+}
+
+fn parse_binary(p: &mut Parser) -> SyntaxResult<Expression> {
+    let first = parse_operand(p)?;
+    p.skip_trivia();
+    let mut rest = vec![];
+    while let Some(operator) = infix_operator(p) {
+        p.descend(1)?;
+        let start = p.offset();
+        p.bump();
+        let operator_span = Span(start, p.offset());
+        rest.push((operator, operator_span, parse_operand(p)?));
+        let checkpoint = p.checkpoint();
+        p.skip_trivia();
+        if infix_operator(p).is_none() {
+            p.restore(checkpoint);
+        }
+    }
+    Ok(climb(first, 0, &mut rest.into_iter().peekable()))
+}
+
+fn precedence(operator: &BinaryOperator) -> u8 {
+    match operator {
+        BinaryOperator::BarBar => 1,
+        BinaryOperator::AmpAmp => 2,
+        BinaryOperator::Eq
+        | BinaryOperator::Ne
+        | BinaryOperator::Tilde
+        | BinaryOperator::NotTilde => 3,
+        BinaryOperator::Lt | BinaryOperator::Gt | BinaryOperator::Le | BinaryOperator::Ge => 4,
+        BinaryOperator::Add | BinaryOperator::Sub => 5,
+        BinaryOperator::Mul | BinaryOperator::Div => 6,
+    }
 }
 
-fn handle_primary(pair: Pair<Rule>) -> ParseResult<Expression> {
-    debug_assert!(pair.as_rule() == Rule::Primary);
-    let inner = pair.into_inner();
-    for pair in inner {
-        match pair.as_rule() {
-            Rule::Literal => return literal::handle(pair).map(Expression::Literal),
-            Rule::Concat => return handle_concat(pair),
-            Rule::Expr => return handle(pair),
-            Rule::COMMENT => {}
-            _ => unreachable!("Unexpected rule: {:?}", pair.as_rule()),
+/// Folds the flat `operand (operator operand)*` list into a tree by precedence climbing.
+fn climb(
+    mut lhs: Expression,
+    min: u8,
+    rest: &mut Peekable<impl Iterator<Item = (BinaryOperator, Span, Expression)>>,
+) -> Expression {
+    while let Some((operator, operator_span, mut rhs)) =
+        rest.next_if(|(op, _, _)| precedence(op) >= min)
+    {
+        while rest
+            .peek()
+            .is_some_and(|(next, _, _)| precedence(next) > precedence(&operator))
+        {
+            rhs = climb(rhs, precedence(&operator) + 1, rest);
         }
+        lhs = Expression::Binary(BinaryExpression {
+            span: Span(lhs.span().0, rhs.span().1),
+            lhs: Box::new(lhs),
+            operator,
+            operator_span,
+            rhs: Box::new(rhs),
+        });
     }
-    unreachable!("No primary found")
+    lhs
 }
 
-#[derive(Clone, Debug)]
-struct ConcatEntry {
-    expr: Expression,
-    span: Span,
+fn infix_operator(p: &Parser) -> Option<BinaryOperator> {
+    let operator = match p.peek()?.kind {
+        TokenKind::Eq => BinaryOperator::Eq,
+        TokenKind::Ne => BinaryOperator::Ne,
+        TokenKind::Lt => BinaryOperator::Lt,
+        TokenKind::Le => BinaryOperator::Le,
+        TokenKind::Gt => BinaryOperator::Gt,
+        TokenKind::Ge => BinaryOperator::Ge,
+        TokenKind::AmpAmp => BinaryOperator::AmpAmp,
+        TokenKind::BarBar => BinaryOperator::BarBar,
+        TokenKind::Plus => BinaryOperator::Add,
+        TokenKind::Minus => BinaryOperator::Sub,
+        TokenKind::Star => BinaryOperator::Mul,
+        TokenKind::Slash => BinaryOperator::Div,
+        TokenKind::Tilde => BinaryOperator::Tilde,
+        TokenKind::NotTilde => BinaryOperator::NotTilde,
+        _ => return None,
+    };
+    Some(operator)
 }
 
-fn handle_concat(pair: Pair<Rule>) -> ParseResult<Expression> {
-    debug_assert!(pair.as_rule() == Rule::Concat);
-    let inner = pair.into_inner();
-    let mut entries = vec![];
-    let mut errors = vec![];
-    for pair in inner {
-        let span = convert_span(pair.as_span());
-        match pair.as_rule() {
-            Rule::String => match literal::string::handle(pair) {
-                Ok(s) => entries.push(ConcatEntry {
-                    expr: Expression::Literal(Literal::String(s)),
-                    span,
-                }),
-                Err(e) => errors.extend(e),
-            },
-            Rule::Variable => match variable::handle(pair) {
-                Ok(v) => entries.push(ConcatEntry {
-                    expr: Expression::Variable(v),
-                    span,
-                }),
-                Err(e) => errors.extend(e),
-            },
-            Rule::COMMENT => {}
-            _ => unreachable!("Unexpected rule: {:?}", pair.as_rule()),
+/// A primary with its prefix operators, which apply from the innermost one.
+fn parse_operand(p: &mut Parser) -> SyntaxResult<Expression> {
+    let mut prefixes = vec![];
+    loop {
+        p.skip_trivia();
+        let operator = match p.peek().map(|t| t.kind) {
+            Some(TokenKind::Not) => UnaryOperator::Not,
+            Some(TokenKind::Minus) => UnaryOperator::Neg,
+            _ => break,
+        };
+        p.descend(1)?;
+        let start = p.offset();
+        p.bump();
+        prefixes.push((operator, Span(start, p.offset())));
+    }
+    let mut expr = parse_primary(p)?;
+    for (operator, operator_span) in prefixes.into_iter().rev() {
+        expr = Expression::Unary(UnaryExpression {
+            span: Span(operator_span.0, expr.span().1),
+            operator,
+            operator_span,
+            rhs: Box::new(expr),
+        });
+    }
+    Ok(expr)
+}
+
+fn parse_primary(p: &mut Parser) -> SyntaxResult<Expression> {
+    p.skip_trivia();
+    match p.peek().map(|t| t.kind) {
+        Some(TokenKind::LParen) => p.node(SyntaxKind::ParenExpression, |p| {
+            let start = p.offset();
+            p.bump();
+            let expr = parse(p)?;
+            p.expect(TokenKind::RParen, ")")?;
+            Ok(Expression::Paren(ParenExpression {
+                span: Span(start, p.offset()),
+                expr: Box::new(expr),
+            }))
+        }),
+        Some(kind) if kind.is_string() || kind == TokenKind::Ident => parse_concat(p),
+        Some(TokenKind::Number) => literal::parse(p).map(Expression::Literal),
+        Some(TokenKind::LBrace) => {
+            object::parse(p).map(|o| Expression::Literal(Literal::Object(o)))
         }
+        _ => Err(p.unexpected(&["Expression"])),
     }
-    if errors.is_empty() {
-        Ok(fold_concat(&entries))
-    } else {
-        Err(errors)
+}
+
+fn at_concat_entry(p: &Parser) -> bool {
+    p.peek()
+        .is_some_and(|t| t.kind.is_string() || t.kind == TokenKind::Ident)
+}
+
+/// Juxtaposed strings, variables and calls, e.g. `"a" req.http.b std.tolower("c")`.
+fn parse_concat(p: &mut Parser) -> SyntaxResult<Expression> {
+    let first = parse_concat_entry(p)?;
+    let rest = p.repeat(at_concat_entry, parse_concat_entry)?;
+    if rest.is_empty() {
+        return Ok(first);
+    }
+    let mut parts = vec![first];
+    parts.extend(rest);
+    Ok(Expression::Concat(ConcatExpression {
+        span: Span(parts[0].span().0, parts[parts.len() - 1].span().1),
+        parts,
+    }))
+}
+
+fn parse_concat_entry(p: &mut Parser) -> SyntaxResult<Expression> {
+    p.skip_trivia();
+    if !p.at(TokenKind::Ident) {
+        return string::parse(p).map(|s| Expression::Literal(Literal::String(s)));
+    }
+    if p.lookahead(1).is_some_and(|t| t.kind == TokenKind::LParen) {
+        return p
+            .node(SyntaxKind::CallExpression, parse_call)
+            .map(Expression::Call);
     }
+    variable::parse(p).map(Expression::Variable)
 }
 
-fn fold_concat(tokens: &[ConcatEntry]) -> Expression {
-    if tokens.len() == 1 {
-        tokens[0].expr.clone()
-    } else {
-        Expression::Binary(BinaryExpression {
-            lhs: Box::new(tokens[0].expr.clone()),
-            operator: BinaryOperator::Add,
-            rhs: Box::new(fold_concat(&tokens[1..])),
-            span: Span(tokens[0].span.0, tokens[tokens.len() - 1].span.1),
-        })
+/// `target(argument, ...)`
+pub fn parse_call(p: &mut Parser) -> SyntaxResult<CallExpression> {
+    p.skip_trivia();
+    let start = p.offset();
+    let target = variable::parse(p)?;
+    p.expect(TokenKind::LParen, "(")?;
+    p.skip_trivia();
+    let mut arguments = vec![];
+    if !p.at(TokenKind::RParen) {
+        p.node(SyntaxKind::Arguments, |p| {
+            arguments.push(parse(p)?);
+            loop {
+                let checkpoint = p.checkpoint();
+                p.skip_trivia();
+                if p.eat(TokenKind::Comma).is_none() {
+                    p.restore(checkpoint);
+                    return Ok(());
+                }
+                arguments.push(parse(p)?);
+            }
+        })?;
     }
+    p.expect(TokenKind::RParen, ")")?;
+    Ok(CallExpression {
+        target,
+        arguments,
+        span: Span(start, p.offset()),
+    })
 }
@@ -0,0 +1,568 @@
+//! Re-parsing of edited sources.
+//!
+//! Top-level parsing restarts from scratch at every declaration, so after an edit only the
+//! declarations overlapping it have to be parsed again. Parsing resumes at the end of the last
+//! declaration before the edit and stops as soon as a declaration ends where one ended before
+//! the edit; everything after that is reused with its spans shifted. Diagnostics which may
+//! belong to either of two declarations keep both of them from being reused.
+
+use vcls_ast::*;
+
+use crate::{
+    declaration::table,
+    error::ParseError,
+    lexer,
+    parser::{Dialect, Parser},
+    recovery,
+};
+
+/// Replacement of the source in `span` by `text`, e.g. a change reported by an editor.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TextEdit {
+    pub span: Span,
+    pub text: String,
+}
+
+impl TextEdit {
+    pub fn new(span: Span, text: impl Into<String>) -> Self {
+        Self {
+            span,
+            text: text.into(),
+        }
+    }
+
+    /// The source after the edit.
+    pub fn apply(&self, src: &str) -> String {
+        let mut edited = src.to_string();
+        edited.replace_range(self.span.0..self.span.1, &self.text);
+        edited
+    }
+
+    /// Change of the length of the source.
+    fn delta(&self) -> isize {
+        self.text.len() as isize - (self.span.1 - self.span.0) as isize
+    }
+}
+
+/// Updates the result of `parse_recovering` for the source before `edit` to the result for
+/// `src`, the source after it, re-parsing only the declarations affected by the edit.
+///
+/// The result is the same as `parse_recovering(src)`. An `edit` which cannot have produced
+/// `src` makes the whole source be parsed again.
+pub fn parse_incremental(
+    src: &str,
+    previous: &(Vcl, Vec<ParseError>),
+    edit: &TextEdit,
+) -> (Vcl, Vec<ParseError>) {
+    parse_incremental_with_dialect(src, previous, edit, Dialect::Fastly)
+}
+
+/// Like [`parse_incremental`], for the result of `parse_recovering_with_dialect(_, dialect)`.
+pub fn parse_incremental_with_dialect(
+    src: &str,
+    previous: &(Vcl, Vec<ParseError>),
+    edit: &TextEdit,
+    dialect: Dialect,
+) -> (Vcl, Vec<ParseError>) {
+    if edit.span.0 > edit.span.1 || edit.span.0 + edit.text.len() > src.len() {
+        return recovery::parse(src, dialect);
+    }
+    let (vcl, errors) = previous;
+    let old = &vcl.declarations;
+    let delta = edit.delta();
+    // A declaration ending right where the edit starts may continue into the inserted text.
+    let mut first = old.partition_point(|d| d.span().1 < edit.span.0);
+    // Broken declarations and unclosed subroutines end where the next declaration starts,
+    // so they also depend on the source after them.
+    if first > 0
+        && matches!(
+            old[first - 1],
+            Declaration::Invalid(_) | Declaration::Subroutine(_)
+        )
+    {
+        first -= 1;
+    }
+    // Table checks depend on other declarations, so they are redone for the whole source.
+    let errors: Vec<&ParseError> = errors
+        .iter()
+        .filter(|e| !table::CHECK_CODES.contains(&e.code))
+        .collect();
+    while first > 0 && ambiguous(&errors, old, first) {
+        first -= 1;
+    }
+    let start = first.checked_sub(1).map_or(0, |i| old[i].span().1);
+    // The edit may have completed a heredoc opened before it.
+    if !src.is_char_boundary(start) || lexer::has_unterminated_heredoc(&src[..start]) {
+        return recovery::parse(src, dialect);
+    }
+    let edit_end = edit.span.0 + edit.text.len();
+
+    let mut p = Parser::starting_at(src, start);
+    p.recovering = true;
+    p.dialect = dialect;
+    let mut declarations = old[..first].to_vec();
+    let mut reused = old.len();
+    while let Some(declaration) = recovery::next_declaration(&mut p) {
+        let end = declaration.span().1;
+        declarations.push(declaration);
+        if end < edit_end {
+            continue;
+        }
+        let old_end = (end as isize - delta) as usize;
+        match old.binary_search_by_key(&old_end, |d| d.span().1) {
+            Ok(i) if !ambiguous(&errors, old, i + 1) => {
+                reused = i + 1;
+                break;
+            }
+            _ => {}
+        }
+    }
+    declarations.extend(old[reused..].iter().cloned().map(|mut d| {
+        d.shift(delta);
+        d
+    }));
+
+    // Away from the ambiguous gaps, diagnostics belong to the declaration they start in.
+    let reused_start = if reused < old.len() {
+        old[reused - 1].span().1
+    } else {
+        usize::MAX
+    };
+    let mut new_errors: Vec<ParseError> = errors
+        .iter()
+        .filter(|e| e.span.0 < start)
+        .map(|&e| e.clone())
+        .collect();
+    new_errors.append(&mut p.errors);
+    new_errors.extend(
+        errors
+            .iter()
+            .filter(|e| e.span.0 >= reused_start)
+            .map(|&e| {
+                let mut e = e.clone();
+                e.shift(delta);
+                e
+            }),
+    );
+    let vcl = Vcl { declarations };
+    new_errors.extend(table::check(&vcl));
+    (vcl, new_errors)
+}
+
+/// Whether a diagnostic starts between the end of declaration `i - 1` and the start of
+/// declaration `i`, or after the last one, where it may belong to either.
+///
+/// A declaration reports the token after it when it is broken, which is the first token of the
+/// next one, and the span of a subroutine starts only at its body.
+fn ambiguous(errors: &[&ParseError], old: &[Declaration], i: usize) -> bool {
+    let from = old[i - 1].span().1;
+    let to = old.get(i).map_or(usize::MAX, |d| d.span().0);
+    errors.iter().any(|e| (from..=to).contains(&e.span.0))
+}
+
+/// Moves every span of a reused node by the change of the source length.
+trait Shift {
+    fn shift(&mut self, delta: isize);
+}
+
+impl<T: Shift> Shift for Vec<T> {
+    fn shift(&mut self, delta: isize) {
+        for item in self {
+            item.shift(delta);
+        }
+    }
+}
+
+impl<T: Shift> Shift for Option<T> {
+    fn shift(&mut self, delta: isize) {
+        if let Some(item) = self {
+            item.shift(delta);
+        }
+    }
+}
+
+impl<T: Shift> Shift for Box<T> {
+    fn shift(&mut self, delta: isize) {
+        self.as_mut().shift(delta);
+    }
+}
+
+impl Shift for Span {
+    fn shift(&mut self, delta: isize) {
+        self.0 = (self.0 as isize + delta) as usize;
+        self.1 = (self.1 as isize + delta) as usize;
+    }
+}
+
+impl Shift for ParseError {
+    fn shift(&mut self, delta: isize) {
+        self.span.shift(delta);
+        for related in &mut self.related {
+            related.span.shift(delta);
+        }
+    }
+}
+
+impl Shift for Declaration {
+    fn shift(&mut self, delta: isize) {
+        match self {
+            Self::Include(d) => d.shift(delta),
+            Self::Import(d) => {
+                let ImportDeclaration { span, ident } = d;
+                span.shift(delta);
+                ident.shift(delta);
+            }
+            Self::Subroutine(d) => d.shift(delta),
+            Self::Acl(d) => d.shift(delta),
+            Self::Backend(d) => d.shift(delta),
+            Self::Director(d) => d.shift(delta),
+            Self::PenaltyBox(d) => {
+                let PenaltyBoxDeclaration { span, name } = d;
+                span.shift(delta);
+                name.shift(delta);
+            }
+            Self::RateCounter(d) => {
+                let RateCounterDeclaration { span, name } = d;
+                span.shift(delta);
+                name.shift(delta);
+            }
+            Self::Table(d) => d.shift(delta),
+            Self::Version(d) => d.span.shift(delta),
+            Self::Probe(d) => {
+                let ProbeDeclaration { span, name, config } = d;
+                span.shift(delta);
+                name.shift(delta);
+                config.shift(delta);
+            }
+            Self::Invalid(d) => d.span.shift(delta),
+        }
+    }
+}
+
+impl Shift for IncludeDeclaration {
+    fn shift(&mut self, delta: isize) {
+        let Self { span, path } = self;
+        span.shift(delta);
+        path.shift(delta);
+    }
+}
+
+impl Shift for SubroutineDeclaration {
+    fn shift(&mut self, delta: isize) {
+        let Self {
+            span,
+            name,
+            return_type: _,
+            body,
+        } = self;
+        span.shift(delta);
+        name.shift(delta);
+        body.shift(delta);
+    }
+}
+
+impl Shift for AclDeclaration {
+    fn shift(&mut self, delta: isize) {
+        let Self {
+            span,
+            name,
+            entries,
+        } = self;
+        span.shift(delta);
+        name.shift(delta);
+        entries.shift(delta);
+    }
+}
+
+impl Shift for AclEntry {
+    fn shift(&mut self, delta: isize) {
+        self.span.shift(delta);
+    }
+}
+
+impl Shift for BackendDeclaration {
+    fn shift(&mut self, delta: isize) {
+        let Self {
+            span,
+            name,
+            config,
+            settings: _,
+        } = self;
+        span.shift(delta);
+        name.shift(delta);
+        config.shift(delta);
+    }
+}
+
+impl Shift for DirectorDeclaration {
+    fn shift(&mut self, delta: isize) {
+        let Self {
+            span,
+            name,
+            typ: _,
+            config,
+            directions,
+        } = self;
+        span.shift(delta);
+        name.shift(delta);
+        config.shift(delta);
+        directions.shift(delta);
+    }
+}
+
+impl Shift for TableDeclaration {
+    fn shift(&mut self, delta: isize) {
+        let Self {
+            span,
+            name,
+            typ: _,
+            entries,
+        } = self;
+        span.shift(delta);
+        name.shift(delta);
+        entries.shift(delta);
+    }
+}
+
+impl Shift for TableEntry {
+    fn shift(&mut self, delta: isize) {
+        let Self { span, key, value } = self;
+        span.shift(delta);
+        key.shift(delta);
+        match value {
+            TableValue::Ident(v) => v.shift(delta),
+            TableValue::Literal(l) => l.shift(delta),
+        }
+    }
+}
+
+impl Shift for Literal {
+    fn shift(&mut self, delta: isize) {
+        match self {
+            Self::String(l) => l.shift(delta),
+            Self::Integer(l) => l.span.shift(delta),
+            Self::Float(l) => l.span.shift(delta),
+            Self::Bool(l) => l.span.shift(delta),
+            Self::RTime(l) => l.span.shift(delta),
+            Self::Percentage(l) => l.span.shift(delta),
+            Self::Object(l) => l.shift(delta),
+        }
+    }
+}
+
+impl Shift for StringLiteral {
+    fn shift(&mut self, delta: isize) {
+        let Self { span, tokens } = self;
+        span.shift(delta);
+        tokens.shift(delta);
+    }
+}
+
+impl Shift for Ident {
+    fn shift(&mut self, delta: isize) {
+        self.span.shift(delta);
+    }
+}
+
+impl Shift for StringToken {
+    fn shift(&mut self, delta: isize) {
+        self.span.shift(delta);
+    }
+}
+
+impl Shift for Object {
+    fn shift(&mut self, delta: isize) {
+        let Self { entries, span } = self;
+        span.shift(delta);
+        for (key, value) in entries {
+            key.shift(delta);
+            match value {
+                ObjectValue::Literal(l) => l.shift(delta),
+                ObjectValue::Ident(i) => i.shift(delta),
+            }
+        }
+    }
+}
+
+impl Shift for Statement {
+    fn shift(&mut self, delta: isize) {
+        match self {
+            Self::If(s) => s.shift(delta),
+            Self::Set(s) => {
+                let SetStatement {
+                    span,
+                    target,
+                    operator: _,
+                    value,
+                } = s;
+                span.shift(delta);
+                target.shift(delta);
+                value.shift(delta);
+            }
+            Self::Unset(s) => {
+                s.span.shift(delta);
+                s.target.shift(delta);
+            }
+            Self::Add(s) => {
+                let AddStatement {
+                    span,
+                    target,
+                    value,
+                } = s;
+                span.shift(delta);
+                target.shift(delta);
+                value.shift(delta);
+            }
+            Self::Call(s) => {
+                s.span.shift(delta);
+                s.target.shift(delta);
+            }
+            Self::Declare(s) => {
+                s.span.shift(delta);
+                s.target.shift(delta);
+            }
+            Self::Error(s) => {
+                let ErrorStatement {
+                    span,
+                    status,
+                    message,
+                } = s;
+                span.shift(delta);
+                status.shift(delta);
+                message.shift(delta);
+            }
+            Self::Esi(s) => s.span.shift(delta),
+            Self::Goto(s) => {
+                let GotoStatement { span, label } = s;
+                span.shift(delta);
+                label.shift(delta);
+            }
+            Self::Label(s) => {
+                let LabelStatement { span, name } = s;
+                span.shift(delta);
+                name.shift(delta);
+            }
+            Self::Include(s) => {
+                s.span.shift(delta);
+                s.path.shift(delta);
+            }
+            Self::Log(s) => {
+                s.span.shift(delta);
+                s.message.shift(delta);
+            }
+            Self::Restart(s) => s.span.shift(delta),
+            Self::Return(s) => {
+                s.span.shift(delta);
+                match &mut s.value {
+                    Some(ReturnValue::Expression(e)) => e.shift(delta),
+                    Some(ReturnValue::Action(ReturnAction::Synth(arguments))) => {
+                        arguments.shift(delta)
+                    }
+                    _ => {}
+                }
+            }
+            Self::Synthetic(s) => {
+                s.span.shift(delta);
+                s.value.shift(delta);
+            }
+            Self::FastlyMacro(s) => {
+                let FastlyMacroStatement { span, name } = s;
+                span.shift(delta);
+                name.shift(delta);
+            }
+            Self::New(s) => {
+                let NewStatement {
+                    span,
+                    name,
+                    constructor,
+                } = s;
+                span.shift(delta);
+                name.shift(delta);
+                constructor.shift(delta);
+            }
+            Self::Invalid(s) => s.span.shift(delta),
+        }
+    }
+}
+
+impl Shift for IfStatement {
+    fn shift(&mut self, delta: isize) {
+        let Self {
+            span,
+            condition,
+            body,
+            els,
+        } = self;
+        span.shift(delta);
+        condition.shift(delta);
+        body.shift(delta);
+        match els {
+            Some(ElseStatement::If(s)) => s.shift(delta),
+            Some(ElseStatement::Body(body)) => body.shift(delta),
+            None => {}
+        }
+    }
+}
+
+impl Shift for Expression {
+    fn shift(&mut self, delta: isize) {
+        match self {
+            Self::Literal(e) => e.shift(delta),
+            Self::Variable(e) => e.shift(delta),
+            Self::Binary(e) => {
+                let BinaryExpression {
+                    span,
+                    lhs,
+                    operator: _,
+                    operator_span,
+                    rhs,
+                } = e;
+                span.shift(delta);
+                operator_span.shift(delta);
+                lhs.shift(delta);
+                rhs.shift(delta);
+            }
+            Self::Unary(e) => {
+                let UnaryExpression {
+                    span,
+                    operator: _,
+                    operator_span,
+                    rhs,
+                } = e;
+                span.shift(delta);
+                operator_span.shift(delta);
+                rhs.shift(delta);
+            }
+            Self::Call(e) => e.shift(delta),
+            Self::Concat(e) => {
+                let ConcatExpression { span, parts } = e;
+                span.shift(delta);
+                parts.shift(delta);
+            }
+            Self::Paren(e) => {
+                let ParenExpression { span, expr } = e;
+                span.shift(delta);
+                expr.shift(delta);
+            }
+        }
+    }
+}
+
+impl Shift for CallExpression {
+    fn shift(&mut self, delta: isize) {
+        let Self {
+            span,
+            target,
+            arguments,
+        } = self;
+        span.shift(delta);
+        target.shift(delta);
+        arguments.shift(delta);
+    }
+}
+
+impl Shift for Variable {
+    fn shift(&mut self, delta: isize) {
+        self.span.shift(delta);
+    }
+}
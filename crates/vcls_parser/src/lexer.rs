@@ -0,0 +1,390 @@
+use vcls_ast::Span;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum TokenKind {
+    /// Spaces, tabs and newlines
+    Whitespace,
+    /// `// ...` up to (not including) the newline
+    SlashComment,
+    /// `# ...` up to (not including) the newline
+    HashComment,
+    /// `#FASTLY recv`, which is a statement in a body and a comment elsewhere
+    FastlyMacro,
+    /// `/* ... */`
+    BlockComment,
+    /// `vcl_recv`, `req.http.X-Forwarded-For`, `req.http.Cookie:id`
+    Ident,
+    /// `10`, `1.5`, `1e3`, `0x1F`, `0x1.8p3` (the sign is a separate token)
+    Number,
+    /// `"..."`
+    QuotedString,
+    /// `{"..."}`
+    BracesString,
+    /// `{DELIM"..."DELIM}`
+    HeredocString,
+    LBrace,
+    RBrace,
+    LParen,
+    RParen,
+    Semicolon,
+    Comma,
+    Colon,
+    Dot,
+    /// `=`
+    Assign,
+    /// `==`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `!`
+    Not,
+    /// `~`
+    Tilde,
+    /// `!~`
+    NotTilde,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `&&`
+    AmpAmp,
+    /// `||`
+    BarBar,
+    /// `&`
+    Amp,
+    /// `|`
+    Bar,
+    /// `^`
+    Hat,
+    /// `<<`
+    LShift,
+    /// `>>`
+    RShift,
+    /// `+`
+    Plus,
+    /// `-`
+    Minus,
+    /// `*`
+    Star,
+    /// `/`
+    Slash,
+    /// `%`
+    Percent,
+    /// `+=`, `-=`, `&&=`, ... (`ror=` and `rol=` are an `Ident` followed by `Assign`)
+    CompoundAssign,
+    /// A character which cannot start any token, or an unterminated string/comment
+    Unknown,
+}
+
+impl TokenKind {
+    pub fn is_string(&self) -> bool {
+        matches!(
+            self,
+            TokenKind::QuotedString | TokenKind::BracesString | TokenKind::HeredocString
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+impl Token {
+    /// Whitespace and comments, which the parser skips.
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self.kind,
+            TokenKind::Whitespace
+                | TokenKind::SlashComment
+                | TokenKind::HashComment
+                | TokenKind::FastlyMacro
+                | TokenKind::BlockComment
+        )
+    }
+
+    pub fn text<'a>(&self, src: &'a str) -> &'a str {
+        &src[self.span.0..self.span.1]
+    }
+}
+
+/// Splits `src` into tokens, including whitespace and comments.
+/// Concatenating the text of all tokens always yields `src` again.
+pub fn tokenize(src: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut pos = 0;
+    while pos < src.len() {
+        let (kind, len) = next_token(&src[pos..]);
+        tokens.push(Token {
+            kind,
+            span: Span(pos, pos + len),
+        });
+        pos += len;
+    }
+    tokens
+}
+
+const PUNCTUATIONS: [(&str, TokenKind); 37] = [
+    ("&&=", TokenKind::CompoundAssign),
+    ("||=", TokenKind::CompoundAssign),
+    ("<<=", TokenKind::CompoundAssign),
+    (">>=", TokenKind::CompoundAssign),
+    ("+=", TokenKind::CompoundAssign),
+    ("-=", TokenKind::CompoundAssign),
+    ("*=", TokenKind::CompoundAssign),
+    ("/=", TokenKind::CompoundAssign),
+    ("%=", TokenKind::CompoundAssign),
+    ("&=", TokenKind::CompoundAssign),
+    ("|=", TokenKind::CompoundAssign),
+    ("^=", TokenKind::CompoundAssign),
+    ("==", TokenKind::Eq),
+    ("!=", TokenKind::Ne),
+    ("!~", TokenKind::NotTilde),
+    ("<=", TokenKind::Le),
+    (">=", TokenKind::Ge),
+    ("&&", TokenKind::AmpAmp),
+    ("||", TokenKind::BarBar),
+    ("<<", TokenKind::LShift),
+    (">>", TokenKind::RShift),
+    ("{", TokenKind::LBrace),
+    ("}", TokenKind::RBrace),
+    ("(", TokenKind::LParen),
+    (")", TokenKind::RParen),
+    (";", TokenKind::Semicolon),
+    (",", TokenKind::Comma),
+    (":", TokenKind::Colon),
+    (".", TokenKind::Dot),
+    ("=", TokenKind::Assign),
+    ("!", TokenKind::Not),
+    ("~", TokenKind::Tilde),
+    ("<", TokenKind::Lt),
+    (">", TokenKind::Gt),
+    ("&", TokenKind::Amp),
+    ("|", TokenKind::Bar),
+    ("^", TokenKind::Hat),
+];
+
+const OPERATORS: [(&str, TokenKind); 5] = [
+    ("+", TokenKind::Plus),
+    ("-", TokenKind::Minus),
+    ("*", TokenKind::Star),
+    ("/", TokenKind::Slash),
+    ("%", TokenKind::Percent),
+];
+
+fn next_token(rest: &str) -> (TokenKind, usize) {
+    let bytes = rest.as_bytes();
+    let first = bytes[0];
+    if first.is_ascii_whitespace() {
+        let len = bytes.iter().take_while(|b| b.is_ascii_whitespace()).count();
+        return (TokenKind::Whitespace, len);
+    }
+    if rest.starts_with("//") {
+        return (TokenKind::SlashComment, line_len(rest));
+    }
+    if first == b'#' {
+        let len = line_len(rest);
+        if is_fastly_macro(&rest[..len]) {
+            return (TokenKind::FastlyMacro, len);
+        }
+        return (TokenKind::HashComment, len);
+    }
+    if let Some(body) = rest.strip_prefix("/*") {
+        return match body.find("*/") {
+            Some(i) => (TokenKind::BlockComment, i + 4),
+            None => (TokenKind::Unknown, rest.len()),
+        };
+    }
+    if first == b'"' {
+        return match rest[1..].find('"') {
+            Some(i) => (TokenKind::QuotedString, i + 2),
+            None => (TokenKind::Unknown, rest.len()),
+        };
+    }
+    if first == b'{' {
+        if let Some(token) = long_string(rest) {
+            return token;
+        }
+    }
+    if first.is_ascii_alphabetic() {
+        return (TokenKind::Ident, ident_len(rest));
+    }
+    if first.is_ascii_digit() {
+        return (TokenKind::Number, number_len(rest));
+    }
+    for (punctuation, kind) in PUNCTUATIONS.iter().chain(OPERATORS.iter()) {
+        if rest.starts_with(punctuation) {
+            return (*kind, punctuation.len());
+        }
+    }
+    let len = rest.chars().next().map(char::len_utf8).unwrap_or(1);
+    (TokenKind::Unknown, len)
+}
+
+fn line_len(rest: &str) -> usize {
+    rest.find(['\n', '\r']).unwrap_or(rest.len())
+}
+
+/// `#FASTLY name`, only followed by whitespace on its line.
+fn is_fastly_macro(line: &str) -> bool {
+    let Some(rest) = line.strip_prefix("#FASTLY") else {
+        return false;
+    };
+    let name = rest.trim_matches([' ', '\t']);
+    rest.starts_with([' ', '\t'])
+        && !name.is_empty()
+        && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+/// `{"..."}` or `{DELIM"..."DELIM}`
+fn long_string(rest: &str) -> Option<(TokenKind, usize)> {
+    let inner = &rest[1..];
+    if let Some(body) = inner.strip_prefix('"') {
+        return Some(match body.find("\"}") {
+            Some(i) => (TokenKind::BracesString, i + 4),
+            None => (TokenKind::Unknown, rest.len()),
+        });
+    }
+    let delimiter = heredoc_delimiter(rest)?;
+    let terminator = format!("\"{}}}", delimiter);
+    let body_start = 1 + delimiter.len() + 1;
+    // The terminator starts with a quote of its own, so `{DELIM"DELIM}` is unterminated.
+    rest[body_start..]
+        .find(&terminator)
+        .map(|i| (TokenKind::HeredocString, body_start + i + terminator.len()))
+}
+
+/// `DELIM` of `{DELIM"`.
+fn heredoc_delimiter(rest: &str) -> Option<&str> {
+    let inner = &rest[1..];
+    let len = inner
+        .bytes()
+        .take_while(|b| b.is_ascii_alphabetic() || *b == b'_')
+        .count();
+    (len > 0 && inner[len..].starts_with('"')).then_some(&inner[..len])
+}
+
+/// Whether `src` has a `{DELIM"` without its `"DELIM}`, which is lexed as `{` but would start
+/// a heredoc if the terminator was added after it.
+pub fn has_unterminated_heredoc(src: &str) -> bool {
+    src.match_indices('{')
+        .any(|(i, _)| heredoc_delimiter(&src[i..]).is_some() && long_string(&src[i..]).is_none())
+}
+
+/// An identifier with its `.property` accesses and an optional `:subfield`.
+fn ident_len(rest: &str) -> usize {
+    let bytes = rest.as_bytes();
+    let word = |from: usize, extra: &[u8]| {
+        bytes[from..]
+            .iter()
+            .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_' || extra.contains(b))
+            .count()
+    };
+    let mut len = word(0, &[]);
+    while len + 1 < bytes.len() && bytes[len] == b'.' && bytes[len + 1].is_ascii_alphabetic() {
+        len += 1 + word(len + 1, b"-");
+    }
+    if len + 1 < bytes.len() && bytes[len] == b':' && bytes[len + 1].is_ascii_alphabetic() {
+        len += 1 + word(len + 1, &[]);
+    }
+    len
+}
+
+fn number_len(rest: &str) -> usize {
+    let bytes = rest.as_bytes();
+    let count =
+        |from: usize, pred: fn(&u8) -> bool| bytes[from..].iter().take_while(|b| pred(b)).count();
+    let hex = rest.starts_with("0x") && bytes.get(2).is_some_and(u8::is_ascii_hexdigit);
+    let (digit, exponent): (fn(&u8) -> bool, u8) = if hex {
+        (u8::is_ascii_hexdigit, b'p')
+    } else {
+        (u8::is_ascii_digit, b'e')
+    };
+    let mut len = if hex { 2 } else { 0 };
+    len += count(len, digit);
+    if bytes.get(len) == Some(&b'.') && bytes.get(len + 1).is_some_and(digit) {
+        len += 1 + count(len + 1, digit);
+    }
+    if bytes.get(len) == Some(&exponent) {
+        let sign = usize::from(matches!(bytes.get(len + 1), Some(b'+' | b'-')));
+        if bytes.get(len + 1 + sign).is_some_and(u8::is_ascii_digit) {
+            len += 1 + sign + count(len + 1 + sign, u8::is_ascii_digit);
+        }
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(src: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(src)
+            .into_iter()
+            .map(|t| (t.kind, t.text(src)))
+            .collect()
+    }
+
+    #[test]
+    fn lossless() {
+        let src = "sub vcl_recv {\n  # comment\n  set req.http.X-Y:z = {\"a\"} {EOF\"b\"EOF} 0x1.8p3; /* c */\n}";
+        let text: String = tokenize(src).iter().map(|t| t.text(src)).collect();
+        assert_eq!(text, src);
+    }
+
+    #[test]
+    fn tokens() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds(r#"if (req.http.X-Y:z !~ "a"{X""X}) { set x &&= 1e-3; }"#),
+            vec![
+                (Ident, "if"),
+                (Whitespace, " "),
+                (LParen, "("),
+                (Ident, "req.http.X-Y:z"),
+                (Whitespace, " "),
+                (NotTilde, "!~"),
+                (Whitespace, " "),
+                (QuotedString, "\"a\""),
+                (HeredocString, "{X\"\"X}"),
+                (RParen, ")"),
+                (Whitespace, " "),
+                (LBrace, "{"),
+                (Whitespace, " "),
+                (Ident, "set"),
+                (Whitespace, " "),
+                (Ident, "x"),
+                (Whitespace, " "),
+                (CompoundAssign, "&&="),
+                (Whitespace, " "),
+                (Number, "1e-3"),
+                (Semicolon, ";"),
+                (Whitespace, " "),
+                (RBrace, "}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        for src in ["0", "10", "1.5", "1e5", "1E", "0x1F", "0x1.8p-3", "0x"] {
+            let tokens = tokenize(src);
+            assert_eq!(tokens[0].kind, TokenKind::Number, "{}", src);
+        }
+        assert_eq!(kinds("10s")[0], (TokenKind::Number, "10"));
+        assert_eq!(kinds("0xZ")[0], (TokenKind::Number, "0"));
+        assert_eq!(kinds("1.")[0], (TokenKind::Number, "1"));
+    }
+
+    #[test]
+    fn unterminated() {
+        assert_eq!(kinds("\"abc"), vec![(TokenKind::Unknown, "\"abc")]);
+        assert_eq!(kinds("/* abc"), vec![(TokenKind::Unknown, "/* abc")]);
+        assert_eq!(kinds("{\"abc"), vec![(TokenKind::Unknown, "{\"abc")]);
+    }
+}
@@ -1,37 +1,167 @@
-use pest::Parser;
 use vcls_ast::*;
 
+pub mod cst;
 mod declaration;
-mod error;
+pub mod error;
 mod expression;
+mod incremental;
+pub mod lexer;
+pub mod line_index;
 mod literal;
+mod parser;
+mod recovery;
 mod statement;
 mod utils;
 mod variable;
 
-#[derive(pest_derive::Parser)]
-#[grammar = "./fastly-vcl.pest"]
-struct VclParser;
+use parser::{Parser, SyntaxResult};
 
-pub type ParseResult<T> = Result<T, Vec<error::ParseError>>;
+pub use error::{ErrorCode, ParseError, Severity};
+pub use incremental::{parse_incremental, parse_incremental_with_dialect, TextEdit};
+pub use line_index::{Encoding, LineCol, LineIndex};
+pub use parser::Dialect;
 
+pub type ParseResult<T> = Result<T, Vec<ParseError>>;
+
+/// Parses `src` as Fastly VCL, failing on the first syntax error.
+///
+/// Warnings do not fail the parse; use [`parse_recovering`] to see them.
 pub fn parse(src: &str) -> ParseResult<Vcl> {
-    let mut errors = vec![];
-    let pairs = VclParser::parse(Rule::Vcl, src).map_err(|e| {
-        vec![error::ParseError {
-            message: e.to_string(),
-        }]
-    })?;
+    parse_with_dialect(src, Dialect::Fastly)
+}
+
+/// Parses `src` as `dialect`, failing on the first syntax error.
+pub fn parse_with_dialect(src: &str, dialect: Dialect) -> ParseResult<Vcl> {
+    let mut p = Parser::new(src);
+    p.dialect = dialect;
+    let vcl = parse_vcl(&mut p);
+    into_result(p, vcl).map(|(vcl, _)| vcl)
+}
+
+/// Parses `src` into a lossless syntax tree, which keeps every comment and whitespace,
+/// together with the typed AST.
+pub fn parse_lossless(src: &str) -> ParseResult<cst::SyntaxTree> {
+    parse_lossless_with_dialect(src, Dialect::Fastly)
+}
+
+/// `parse_lossless` for `dialect`.
+pub fn parse_lossless_with_dialect(src: &str, dialect: Dialect) -> ParseResult<cst::SyntaxTree> {
+    let mut p = Parser::lossless(src);
+    p.dialect = dialect;
+    let vcl = parse_vcl(&mut p);
+    into_result(p, vcl).map(|(vcl, p)| p.finish(vcl))
+}
+
+/// Parses `src` as Fastly VCL, recovering from syntax errors.
+///
+/// Always returns a best-effort `Vcl` in which unparsable parts are replaced by
+/// `Declaration::Invalid` / `Statement::Invalid`, together with every diagnostic.
+pub fn parse_recovering(src: &str) -> (Vcl, Vec<ParseError>) {
+    recovery::parse(src, Dialect::Fastly)
+}
+
+/// `parse_recovering` for `dialect`.
+pub fn parse_recovering_with_dialect(src: &str, dialect: Dialect) -> (Vcl, Vec<ParseError>) {
+    recovery::parse(src, dialect)
+}
+
+/// Parses `src` as a single expression, e.g. `req.http.host ~ "example"`.
+pub fn parse_expression(src: &str) -> ParseResult<Expression> {
+    parse_expression_with_dialect(src, Dialect::Fastly)
+}
+
+/// `parse_expression` for `dialect`.
+pub fn parse_expression_with_dialect(src: &str, dialect: Dialect) -> ParseResult<Expression> {
+    let mut p = Parser::new(src);
+    p.dialect = dialect;
+    let expression = expression::parse(&mut p).and_then(|e| expect_eof(&mut p).map(|_| e));
+    into_result(p, expression).map(|(expression, _)| expression)
+}
+
+/// Parses `src` as the statements of a `VOID` subroutine body without its braces,
+/// which is what a Fastly VCL snippet contains.
+pub fn parse_statements(src: &str) -> ParseResult<Vec<Statement>> {
+    parse_statements_with_dialect(src, Dialect::Fastly)
+}
+
+/// `parse_statements` for `dialect`.
+pub fn parse_statements_with_dialect(src: &str, dialect: Dialect) -> ParseResult<Vec<Statement>> {
+    let mut p = Parser::new(src);
+    p.dialect = dialect;
+    let statements = parse_statement_list(&mut p);
+    if let Ok(statements) = &statements {
+        let errors = statement::goto::validate(statements);
+        p.errors.extend(errors);
+    }
+    into_result(p, statements).map(|(statements, _)| statements)
+}
+
+/// Parses `src` as a single declaration. References to other declarations, such as
+/// backends in a `BACKEND` table, are not resolved.
+pub fn parse_declaration(src: &str) -> ParseResult<Declaration> {
+    parse_declaration_with_dialect(src, Dialect::Fastly)
+}
+
+/// `parse_declaration` for `dialect`.
+pub fn parse_declaration_with_dialect(src: &str, dialect: Dialect) -> ParseResult<Declaration> {
+    let mut p = Parser::new(src);
+    p.dialect = dialect;
+    let declaration = declaration::parse(&mut p)
+        .and_then(|d| expect_eof(&mut p).map(|_| d))
+        .map(|declaration| {
+            let mut vcl = Vcl {
+                declarations: vec![declaration],
+            };
+            p.errors.extend(declaration::table::check_unresolved(&vcl));
+            vcl.declarations.remove(0)
+        });
+    into_result(p, declaration).map(|(declaration, _)| declaration)
+}
+
+fn parse_statement_list(p: &mut Parser) -> SyntaxResult<Vec<Statement>> {
+    let mut statements = vec![];
+    loop {
+        p.skip_trivia_to_statement();
+        if p.at_eof() {
+            return Ok(statements);
+        }
+        if !statement::at_statement(p) {
+            return Err(p.unexpected(&["Statement"]));
+        }
+        statements.push(statement::parse(p)?);
+    }
+}
+
+/// Fails unless only trivia is left.
+fn expect_eof(p: &mut Parser) -> SyntaxResult<()> {
+    p.skip_trivia();
+    if p.at_eof() {
+        Ok(())
+    } else {
+        Err(p.unexpected(&["end of input"]))
+    }
+}
+
+fn parse_vcl(p: &mut Parser) -> SyntaxResult<Vcl> {
     let mut declarations = vec![];
-    for pair in pairs {
-        match pair.as_rule() {
-            Rule::Declaration => match declaration::handle(pair) {
-                Ok(declaration) => declarations.push(declaration),
-                Err(mut err) => errors.append(&mut err),
-            },
-            Rule::EOI => {}
-            _ => unreachable!("Unexpected rule: {:?}", pair.as_rule()),
+    loop {
+        p.skip_trivia();
+        if p.at_eof() {
+            let vcl = Vcl { declarations };
+            p.errors.extend(declaration::table::check(&vcl));
+            return Ok(vcl);
+        }
+        declarations.push(declaration::parse(p)?);
+    }
+}
+
+fn into_result<T>(mut p: Parser, result: SyntaxResult<T>) -> ParseResult<(T, Parser)> {
+    match result {
+        Ok(value) if p.errors.iter().all(|e| e.severity != Severity::Error) => Ok((value, p)),
+        Ok(_) => Err(p.errors),
+        Err(error) => {
+            p.errors.push(error);
+            Err(p.errors)
         }
     }
-    Ok(Vcl { declarations })
 }
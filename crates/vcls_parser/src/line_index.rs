@@ -0,0 +1,148 @@
+//! Conversion between byte offsets and line/column positions.
+
+use std::fmt;
+
+use vcls_ast::Span;
+
+/// Unit of columns: bytes for Rust strings, UTF-16 code units for LSP clients.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Encoding {
+    Utf8,
+    Utf16,
+}
+
+/// Zero-based line and column.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct LineCol {
+    pub line: u32,
+    pub col: u32,
+}
+
+impl LineCol {
+    pub fn new(line: u32, col: u32) -> Self {
+        Self { line, col }
+    }
+}
+
+/// One-based `line:col`, as editors and compilers print positions.
+impl fmt::Display for LineCol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line + 1, self.col + 1)
+    }
+}
+
+/// Start offsets of the lines of a source, and where it has multi-byte characters.
+///
+/// Lines end with `\n`; a preceding `\r` is the last column of its line.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LineIndex {
+    len: usize,
+    line_starts: Vec<usize>,
+    /// Byte offset and UTF-8 length of every non-ASCII character
+    wide_chars: Vec<(usize, usize)>,
+}
+
+impl LineIndex {
+    pub fn new(src: &str) -> Self {
+        let mut line_starts = vec![0];
+        let mut wide_chars = vec![];
+        for (offset, c) in src.char_indices() {
+            if c == '\n' {
+                line_starts.push(offset + 1);
+            } else if !c.is_ascii() {
+                wide_chars.push((offset, c.len_utf8()));
+            }
+        }
+        Self {
+            len: src.len(),
+            line_starts,
+            wide_chars,
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Position of the character at `offset`, which is clamped to the end of the source.
+    /// An offset inside a multi-byte character counts that character as before it.
+    pub fn line_col(&self, offset: usize, encoding: Encoding) -> LineCol {
+        let offset = offset.min(self.len);
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line];
+        let col = match encoding {
+            Encoding::Utf8 => offset - start,
+            Encoding::Utf16 => self.wide_chars_in(start, offset).fold(
+                offset - start,
+                |col, &(char_offset, len)| {
+                    let len = len.min(offset - char_offset);
+                    col - len + utf16_len(len)
+                },
+            ),
+        };
+        LineCol::new(line as u32, col as u32)
+    }
+
+    /// Byte offset of `pos`, or `None` if it is past the end of its line or inside a character.
+    pub fn offset(&self, pos: LineCol, encoding: Encoding) -> Option<usize> {
+        let line = pos.line as usize;
+        let start = *self.line_starts.get(line)?;
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .map_or(self.len, |next| next - 1);
+        let mut offset = start;
+        let mut col = 0;
+        for &(char_offset, len) in self.wide_chars_in(start, end) {
+            let ascii = (char_offset - offset) as u32;
+            if pos.col <= col + ascii {
+                break;
+            }
+            col += ascii;
+            offset = char_offset;
+            let width = match encoding {
+                Encoding::Utf8 => len as u32,
+                Encoding::Utf16 => utf16_len(len) as u32,
+            };
+            if pos.col < col + width {
+                return None;
+            }
+            col += width;
+            offset += len;
+        }
+        let offset = offset + (pos.col - col) as usize;
+        (offset <= end).then_some(offset)
+    }
+
+    /// Start and end position of `span`.
+    pub fn range(&self, span: Span, encoding: Encoding) -> (LineCol, LineCol) {
+        (
+            self.line_col(span.0, encoding),
+            self.line_col(span.1, encoding),
+        )
+    }
+
+    /// The span between two positions, if both are valid.
+    pub fn span(&self, start: LineCol, end: LineCol, encoding: Encoding) -> Option<Span> {
+        let start = self.offset(start, encoding)?;
+        let end = self.offset(end, encoding)?;
+        (start <= end).then_some(Span(start, end))
+    }
+
+    fn wide_chars_in(&self, start: usize, end: usize) -> impl Iterator<Item = &(usize, usize)> {
+        let from = self
+            .wide_chars
+            .partition_point(|&(offset, _)| offset < start);
+        let to = self.wide_chars.partition_point(|&(offset, _)| offset < end);
+        self.wide_chars[from..to].iter()
+    }
+}
+
+/// UTF-16 code units of a character with `len` UTF-8 bytes.
+fn utf16_len(len: usize) -> usize {
+    if len == 4 {
+        2
+    } else {
+        1
+    }
+}
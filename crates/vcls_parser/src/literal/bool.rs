@@ -1,14 +1,27 @@
-use pest::iterators::Pair;
+use vcls_ast::{BoolLiteral, Span};
 
-use crate::{error::ParseError, ParseResult, Rule};
+use crate::{
+    cst::SyntaxKind,
+    parser::{Parser, SyntaxResult},
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<bool> {
-    debug_assert!(pair.as_rule() == Rule::Bool);
-    match pair.as_str() {
-        "true" => Ok(true),
-        "false" => Ok(false),
-        _ => Err(vec![ParseError {
-            message: "Failed to parse boolean".to_string(),
-        }]),
-    }
+pub fn at_bool(p: &Parser) -> bool {
+    matches!(p.keyword(), Some("true" | "false"))
+}
+
+pub fn parse(p: &mut Parser) -> SyntaxResult<BoolLiteral> {
+    p.node(SyntaxKind::Literal, |p| {
+        p.skip_trivia();
+        let start = p.offset();
+        let value = match p.keyword() {
+            Some("true") => true,
+            Some("false") => false,
+            _ => return Err(p.unexpected(&["true", "false"])),
+        };
+        p.bump();
+        Ok(BoolLiteral {
+            value,
+            span: Span(start, p.offset()),
+        })
+    })
 }
@@ -1,26 +1,26 @@
-use pest::iterators::Pair;
-use vcls_ast::{BoolLiteral, Literal, RTimeLiteral};
+use vcls_ast::Literal;
 
-use crate::{utils::convert_span, ParseResult, Rule};
+use crate::{
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+};
 pub mod bool;
 pub mod number;
 pub mod object;
+pub mod percentage;
 pub mod rtime;
 pub mod string;
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<Literal> {
-    let span = convert_span(pair.as_span());
-    match pair.as_rule() {
-        Rule::Literal => handle(
-            pair.into_inner()
-                .find(|p| p.as_rule() != Rule::COMMENT)
-                .unwrap(),
-        ),
-        Rule::String => string::handle(pair).map(Literal::String),
-        Rule::Object => object::handle(pair).map(Literal::Object),
-        Rule::RTime => rtime::handle(pair).map(|r| Literal::RTime(RTimeLiteral { value: r, span })),
-        Rule::Number => number::handle(pair),
-        Rule::Bool => bool::handle(pair).map(|b| Literal::Bool(BoolLiteral { value: b, span })),
-        _ => Err(vec![]),
+/// A string, RTIME, number or bool value, where numbers may be negative.
+pub fn parse(p: &mut Parser) -> SyntaxResult<Literal> {
+    p.skip_trivia();
+    match p.peek().map(|t| t.kind) {
+        Some(kind) if kind.is_string() => string::parse(p).map(Literal::String),
+        Some(TokenKind::Ident) if bool::at_bool(p) => bool::parse(p).map(Literal::Bool),
+        Some(TokenKind::Number | TokenKind::Minus) if rtime::at_rtime(p) => {
+            rtime::parse(p).map(Literal::RTime)
+        }
+        Some(TokenKind::Number | TokenKind::Minus) => number::parse(p),
+        _ => Err(p.unexpected(&["Literal"])),
     }
 }
@@ -1,30 +1,109 @@
-use pest::iterators::Pair;
-use vcls_ast::{FloatLiteral, IntegerLiteral, Literal};
+use vcls_ast::{FloatLiteral, IntegerLiteral, Literal, Span};
 
-use crate::{error::ParseError, utils::convert_span, ParseResult, Rule};
+use crate::{
+    cst::SyntaxKind,
+    error::{ErrorCode, ParseError},
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+};
 
-// TODO: Handle hexadecimals
-pub fn handle(pair: Pair<Rule>) -> ParseResult<Literal> {
-    debug_assert!(pair.as_rule() == Rule::Number);
-    let span = convert_span(pair.as_span());
-    let num = pair.as_str();
-    if num.contains('.') {
-        Ok(Literal::Float(FloatLiteral {
-            value: num.parse().map_err(|e| {
-                vec![ParseError {
-                    message: format!("Failed to parse float: {}", e),
-                }]
-            })?,
-            span,
-        }))
-    } else {
+/// `10`, `-1.5`, `1e3`, `0x1F` or `0x1.8p3`.
+///
+/// Literals with a fraction or an exponent are FLOAT, the others INTEGER.
+pub fn parse(p: &mut Parser) -> SyntaxResult<Literal> {
+    p.node(SyntaxKind::Literal, |p| {
+        p.skip_trivia();
+        let start = p.offset();
+        if p.at(TokenKind::Minus) && p.peek_nth(1).is_some_and(|t| t.kind == TokenKind::Number) {
+            p.bump();
+        }
+        p.expect(TokenKind::Number, "Number")?;
+        let span = Span(start, p.offset());
+        let raw = &p.src()[span.0..span.1];
+        let (negative, unsigned) = match raw.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, raw),
+        };
+        let hex = unsigned.strip_prefix("0x");
+        let is_float = match hex {
+            Some(digits) => digits.contains(['.', 'p']),
+            None => unsigned.contains(['.', 'e']),
+        };
+        if is_float {
+            let value = match hex {
+                Some(digits) => parse_hex_float(digits),
+                None => unsigned.parse().unwrap_or(f64::INFINITY),
+            };
+            if value.is_infinite() {
+                p.errors.push(
+                    ParseError::new(
+                        ErrorCode::InvalidNumber,
+                        span,
+                        format!("Float literal `{}` is out of range", raw),
+                    )
+                    .with_note("FLOAT is a 64-bit IEEE 754 floating point number"),
+                );
+            }
+            let value = if negative { -value } else { value };
+            return Ok(Literal::Float(FloatLiteral {
+                value,
+                span,
+                raw: raw.to_string(),
+            }));
+        }
+        let value = parse_integer(
+            negative,
+            hex.unwrap_or(unsigned),
+            if hex.is_some() { 16 } else { 10 },
+        )
+        .unwrap_or_else(|| {
+            p.errors.push(
+                ParseError::new(
+                    ErrorCode::InvalidNumber,
+                    span,
+                    format!("Integer literal `{}` is out of range for INTEGER", raw),
+                )
+                .with_note(format!(
+                    "INTEGER is a 64-bit signed integer from {} to {}",
+                    i64::MIN,
+                    i64::MAX
+                )),
+            );
+            0
+        });
         Ok(Literal::Integer(IntegerLiteral {
-            value: num.parse().map_err(|e| {
-                vec![ParseError {
-                    message: format!("Failed to parse integer: {}", e),
-                }]
-            })?,
+            value,
             span,
+            raw: raw.to_string(),
         }))
+    })
+}
+
+/// `None` if the value does not fit in an `i64`.
+fn parse_integer(negative: bool, digits: &str, radix: u32) -> Option<i64> {
+    let magnitude = u64::from_str_radix(digits, radix).ok()?;
+    if negative {
+        0i64.checked_sub_unsigned(magnitude)
+    } else {
+        i64::try_from(magnitude).ok()
+    }
+}
+
+/// `1.8p3` (after `0x`) is `0x18 / 16 * 2^3`.
+fn parse_hex_float(digits: &str) -> f64 {
+    let (mantissa, exponent) = match digits.split_once('p') {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse().unwrap_or(i32::MAX)),
+        None => (digits, 0),
+    };
+    let (int, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let value = int
+        .chars()
+        .chain(fraction.chars())
+        .filter_map(|c| c.to_digit(16))
+        .fold(0.0, |value, digit| value * 16.0 + f64::from(digit));
+    if value == 0.0 {
+        return 0.0;
     }
+    let exponent = exponent.saturating_sub((fraction.len() as i32).saturating_mul(4));
+    value * 2f64.powi(exponent)
 }
@@ -1,74 +1,84 @@
-use pest::iterators::Pair;
-use vcls_ast::{BoolLiteral, Literal, Object, ObjectValue, RTimeLiteral};
+use vcls_ast::{Ident, Literal, Object, ObjectValue, Span};
 
 use crate::{
-    error::ParseError,
-    literal::{bool, number, object, rtime, string},
-    utils::convert_span,
-    ParseResult, Rule,
+    cst::SyntaxKind,
+    lexer::TokenKind,
+    literal::{self, bool, percentage},
+    parser::{Parser, SyntaxResult},
 };
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<Object> {
-    debug_assert!(pair.as_rule() == Rule::Object);
-    let span = convert_span(pair.as_span());
-    let inner = pair.into_inner();
-    let mut entries = vec![];
-    let mut errors = vec![];
-    for pair in inner {
-        match pair.as_rule() {
-            Rule::ObjectEntry => match handle_object_entry(pair) {
-                Ok(e) => entries.push(e),
-                Err(e) => errors.extend(e),
-            },
-            Rule::COMMENT => {}
-            Rule::EOI => {}
-            _ => unreachable!("Unexpected rule: {:?}", pair.as_rule()),
+/// Location of an object entry, with those of the entries of its value if that is an object.
+pub struct EntrySpan {
+    pub span: Span,
+    pub entries: Vec<EntrySpan>,
+}
+
+/// `{ .key = value; ... }`
+pub fn parse(p: &mut Parser) -> SyntaxResult<Object> {
+    parse_spanned(p).map(|(object, _)| object)
+}
+
+/// `parse`, also returning where each entry is, since `Object` does not record it.
+pub fn parse_spanned(p: &mut Parser) -> SyntaxResult<(Object, Vec<EntrySpan>)> {
+    p.node(SyntaxKind::Object, |p| {
+        p.skip_trivia();
+        let start = p.offset();
+        p.expect(TokenKind::LBrace, "{")?;
+        let mut entries = vec![];
+        let mut spans = vec![];
+        loop {
+            p.skip_trivia();
+            if p.eat(TokenKind::RBrace).is_some() {
+                break;
+            }
+            if !p.at(TokenKind::Dot) {
+                return Err(p.unexpected(&["Property", "}"]));
+            }
+            let (entry, span) = parse_entry_spanned(p)?;
+            entries.push(entry);
+            spans.push(span);
         }
-    }
-    if errors.is_empty() {
-        Ok(Object { entries, span })
-    } else {
-        Err(errors)
-    }
+        let object = Object {
+            entries,
+            span: Span(start, p.offset()),
+        };
+        Ok((object, spans))
+    })
 }
 
-pub fn handle_object_entry(pair: Pair<Rule>) -> ParseResult<(String, ObjectValue)> {
-    debug_assert!(pair.as_rule() == Rule::ObjectEntry);
-    let mut inner = pair.into_inner();
-    let key = inner
-        .find(|p| p.as_rule() == Rule::ObjectKey)
-        .ok_or(vec![ParseError {
-            message: "Object entry must have a key".to_string(),
-        }])?
-        .as_str();
-    let value = inner
-        .find(|p| {
-            let rule = p.as_rule();
-            rule == Rule::Object
-                || rule == Rule::String
-                || rule == Rule::Number
-                || rule == Rule::Bool
-                || rule == Rule::RTime
-                || rule == Rule::Ident
-        })
-        .ok_or(vec![ParseError {
-            message: "Object entry must have a value".to_string(),
-        }])?;
-    let span = convert_span(value.as_span());
-    let value = match value.as_rule() {
-        Rule::Object => ObjectValue::Literal(Literal::Object(object::handle(value)?)),
-        Rule::String => ObjectValue::Literal(Literal::String(string::handle(value)?)),
-        Rule::Number => ObjectValue::Literal(number::handle(value)?),
-        Rule::Bool => ObjectValue::Literal(Literal::Bool(BoolLiteral {
-            value: bool::handle(value)?,
-            span,
-        })),
-        Rule::RTime => ObjectValue::Literal(Literal::RTime(RTimeLiteral {
-            value: rtime::handle(value)?,
-            span,
-        })),
-        Rule::Ident => ObjectValue::Ident(value.as_str().to_string()),
-        _ => unreachable!("Unexpected rule: {:?}", value.as_rule()),
-    };
-    Ok((key[1..].to_string(), value))
+/// `.key = value;` or `.key = { ... }`
+pub fn parse_entry(p: &mut Parser) -> SyntaxResult<(Ident, ObjectValue)> {
+    parse_entry_spanned(p).map(|(entry, _)| entry)
+}
+
+fn parse_entry_spanned(p: &mut Parser) -> SyntaxResult<((Ident, ObjectValue), EntrySpan)> {
+    p.node(SyntaxKind::ObjectEntry, |p| {
+        p.skip_trivia();
+        let start = p.offset();
+        p.expect(TokenKind::Dot, ".")?;
+        let key = p.expect_ident()?;
+        p.expect(TokenKind::Assign, "=")?;
+        p.skip_trivia();
+        if p.at(TokenKind::LBrace) {
+            let (object, entries) = parse_spanned(p)?;
+            let span = EntrySpan {
+                span: Span(start, p.offset()),
+                entries,
+            };
+            return Ok(((key, ObjectValue::Literal(Literal::Object(object))), span));
+        }
+        let value = if percentage::at_percentage(p) {
+            ObjectValue::Literal(Literal::Percentage(percentage::parse(p)?))
+        } else if p.at(TokenKind::Ident) && !bool::at_bool(p) {
+            ObjectValue::Ident(p.expect_ident()?)
+        } else {
+            ObjectValue::Literal(literal::parse(p)?)
+        };
+        p.expect(TokenKind::Semicolon, ";")?;
+        let span = EntrySpan {
+            span: Span(start, p.offset()),
+            entries: vec![],
+        };
+        Ok(((key, value), span))
+    })
 }
@@ -0,0 +1,32 @@
+use vcls_ast::{PercentageLiteral, Span};
+
+use crate::{
+    cst::SyntaxKind,
+    error::{ErrorCode, ParseError},
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+};
+
+/// `50%`, without whitespace before the `%`
+pub fn at_percentage(p: &Parser) -> bool {
+    p.at(TokenKind::Number) && p.peek_nth(1).is_some_and(|t| t.kind == TokenKind::Percent)
+}
+
+pub fn parse(p: &mut Parser) -> SyntaxResult<PercentageLiteral> {
+    p.node(SyntaxKind::Literal, |p| {
+        p.skip_trivia();
+        let start = p.offset();
+        let number = p.expect(TokenKind::Number, "Number")?;
+        p.expect(TokenKind::Percent, "%")?;
+        let span = Span(start, p.offset());
+        let value = p.text(number).parse().unwrap_or_else(|e| {
+            p.errors.push(ParseError::new(
+                ErrorCode::InvalidNumber,
+                span,
+                format!("Failed to parse percentage: {}", e),
+            ));
+            0.0
+        });
+        Ok(PercentageLiteral { value, span })
+    })
+}
@@ -1,36 +1,47 @@
-use pest::iterators::Pair;
-use vcls_ast::RelativeTime;
+use vcls_ast::{RTimeLiteral, RelativeTime, Span};
 
-use crate::{error::ParseError, ParseResult, Rule};
+use crate::{
+    cst::SyntaxKind,
+    error::{ErrorCode, ParseError},
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<RelativeTime> {
-    debug_assert!(pair.as_rule() == Rule::RTime);
-    let mut inner = pair.into_inner();
-    let value = inner
-        .find(|p| p.as_rule() == Rule::RTimeValue)
-        .ok_or(vec![ParseError {
-            message: "RTime must have a value".to_string(),
-        }])?
-        .as_str()
-        .parse()
-        .map_err(|e| {
-            vec![ParseError {
-                message: format!("Failed to parse RTime value: {}", e).to_string(),
-            }]
-        })?;
-    let unit = inner
-        .find(|p| p.as_rule() == Rule::RTimeUnit)
-        .ok_or(vec![ParseError {
-            message: "RTime must have a unit".to_string(),
-        }])?
-        .as_str();
-    match unit {
-        "ms" => Ok(RelativeTime::from_ms(value as u64)),
-        "s" => Ok(RelativeTime::from_sec(value)),
-        "m" => Ok(RelativeTime::from_min(value)),
-        "h" => Ok(RelativeTime::from_hour(value)),
-        "d" => Ok(RelativeTime::from_day(value)),
-        "y" => Ok(RelativeTime::from_year(value)),
-        _ => unreachable!("Unexpected unit: {}", unit),
-    }
+const UNITS: [&str; 6] = ["ms", "s", "m", "h", "d", "y"];
+
+/// A decimal number directly followed by a unit, e.g. `10s` or `-1.5h`.
+pub fn at_rtime(p: &Parser) -> bool {
+    let number = usize::from(p.at(TokenKind::Minus));
+    let is_decimal = |text: &str| text.bytes().all(|b| b.is_ascii_digit() || b == b'.');
+    p.peek_nth(number)
+        .is_some_and(|t| t.kind == TokenKind::Number && is_decimal(p.text(t)))
+        && p.peek_nth(number + 1)
+            .is_some_and(|t| t.kind == TokenKind::Ident && UNITS.contains(&p.text(t)))
+}
+
+pub fn parse(p: &mut Parser) -> SyntaxResult<RTimeLiteral> {
+    p.node(SyntaxKind::Literal, |p| {
+        p.skip_trivia();
+        let start = p.offset();
+        p.eat(TokenKind::Minus);
+        p.expect(TokenKind::Number, "Number")?;
+        match p.eat(TokenKind::Ident) {
+            Some(unit) if UNITS.contains(&p.text(unit)) => {}
+            _ => return Err(p.unexpected(&UNITS)),
+        }
+        let span = Span(start, p.offset());
+        let raw = &p.src()[span.0..span.1];
+        let value = raw.parse().unwrap_or_else(|_| {
+            p.errors.push(
+                ParseError::new(
+                    ErrorCode::InvalidRTime,
+                    span,
+                    format!("RTIME literal `{}` is out of range", raw),
+                )
+                .with_note("RTIME values are limited to about 292 years"),
+            );
+            RelativeTime::ZERO
+        });
+        Ok(RTimeLiteral { value, span })
+    })
 }
@@ -1,65 +1,179 @@
-use pest::iterators::Pair;
-use vcls_ast::{StringLiteral, StringToken};
+use vcls_ast::{Span, StringKind, StringLiteral, StringToken};
 
-use crate::{utils::convert_span, ParseResult, Rule};
+use crate::{
+    cst::SyntaxKind,
+    error::{ErrorCode, ParseError, Severity},
+    lexer::{Token, TokenKind},
+    parser::{Parser, SyntaxResult},
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<StringLiteral> {
-    debug_assert!(pair.as_rule() == Rule::String);
-    let span = convert_span(pair.as_span());
-    let inner = pair.into_inner();
-    let mut tokens = vec![];
-    let mut errors = vec![];
-    for pair in inner {
-        match pair.as_rule() {
-            Rule::StringToken => match handle_string_token(pair) {
-                Ok(token) => tokens.push(token),
-                Err(err) => errors.extend(err),
-            },
-            Rule::COMMENT => {}
-            _ => unreachable!("Unexpected token: {}", pair.as_str()),
+pub fn at_string(p: &Parser) -> bool {
+    p.peek().is_some_and(|t| t.kind.is_string())
+}
+
+/// One or more adjacent string tokens.
+pub fn parse(p: &mut Parser) -> SyntaxResult<StringLiteral> {
+    p.node(SyntaxKind::Literal, |p| {
+        p.skip_trivia();
+        let start = p.offset();
+        let mut tokens = vec![parse_token(p)?];
+        tokens.extend(p.repeat(at_string, parse_token)?);
+        Ok(StringLiteral {
+            tokens,
+            span: Span(start, p.offset()),
+        })
+    })
+}
+
+fn parse_token(p: &mut Parser) -> SyntaxResult<StringToken> {
+    p.skip_trivia();
+    match p.peek() {
+        Some(token) if token.kind.is_string() => {
+            p.bump();
+            Ok(string_token(p, token))
         }
+        _ => Err(p.unexpected(&["String"])),
     }
-    if errors.is_empty() {
-        Ok(StringLiteral { tokens, span })
+}
+
+/// A `"..."` string, as used for paths and ACL entries.
+pub fn parse_quoted(p: &mut Parser) -> SyntaxResult<StringToken> {
+    let token = p.expect(TokenKind::QuotedString, "String")?;
+    Ok(string_token(p, token))
+}
+
+fn string_token(p: &mut Parser, token: Token) -> StringToken {
+    let raw = p.text(token);
+    let (kind, quote_len) = match token.kind {
+        TokenKind::QuotedString => (StringKind::Quoted, 1),
+        TokenKind::BracesString => (StringKind::Braces, 2),
+        _ => {
+            let quote = raw.find('"').unwrap_or(1);
+            let delimiter = raw[1..quote].to_string();
+            (StringKind::Heredoc { delimiter }, quote + 1)
+        }
+    };
+    let contents = &raw[quote_len..raw.len() - quote_len];
+    let kind = if contents.is_empty() {
+        StringKind::Empty
     } else {
-        Err(errors)
+        kind
+    };
+    let value = match kind {
+        StringKind::Quoted => decode(contents, token.span, &mut p.errors),
+        _ => contents.to_string(),
+    };
+    StringToken {
+        span: token.span,
+        kind,
+        value,
+        raw: raw.to_string(),
     }
 }
 
-fn handle_string_token(pair: Pair<Rule>) -> ParseResult<StringToken> {
-    debug_assert!(pair.as_rule() == Rule::StringToken);
-    let span = convert_span(pair.as_span());
-    let inner = pair.into_inner().next().unwrap();
-    match inner.as_rule() {
-        Rule::EmptyString => Ok(StringToken {
-            value: "".to_string(),
-            span,
-        }),
-        Rule::QuotedString => {
-            let quoted = inner.as_str();
-            Ok(StringToken {
-                value: quoted[1..quoted.len() - 1].to_string(),
-                span,
-            })
+/// Decodes the escapes of the contents of a `"..."` string token at `span`:
+/// `%XX` is a byte, `%uXXXX` and `%u{X...}` are code points. Any other `%`, such as the
+/// one of `"100%"`, is kept with a warning.
+fn decode(contents: &str, span: Span, errors: &mut Vec<ParseError>) -> String {
+    let mut bytes = Vec::with_capacity(contents.len());
+    let mut i = 0;
+    while let Some(escape) = contents[i..].find('%') {
+        bytes.extend_from_slice(&contents.as_bytes()[i..i + escape]);
+        i += escape;
+        let (len, decoded) = decode_escape(&contents[i + 1..]);
+        let escape_span = Span(span.0 + 1 + i, span.0 + 2 + i + len);
+        match decoded {
+            Ok(Escape::Byte(byte)) => bytes.push(byte),
+            Ok(Escape::Char(c)) => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            Ok(Escape::Stray(message)) => {
+                errors.push(
+                    ParseError::new(ErrorCode::InvalidEscape, escape_span, message)
+                        .with_severity(Severity::Warning),
+                );
+                bytes.extend_from_slice(&contents.as_bytes()[i..i + 1 + len]);
+            }
+            Err(message) => {
+                errors.push(ParseError::new(
+                    ErrorCode::InvalidEscape,
+                    escape_span,
+                    message,
+                ));
+                bytes.extend_from_slice(&contents.as_bytes()[i..i + 1 + len]);
+            }
         }
-        Rule::HeredocString | Rule::BracesQuotedString => {
-            let quoted = inner.as_str();
-            let quote_len = quoted.find('"').unwrap() + 1;
-            Ok(StringToken {
-                value: quoted[quote_len..quoted.len() - quote_len].to_string(),
-                span,
-            })
-        }
-        _ => unreachable!("Unexpected token: {}", inner.as_str()),
+        i += 1 + len;
     }
+    bytes.extend_from_slice(&contents.as_bytes()[i..]);
+    String::from_utf8(bytes).unwrap_or_else(|e| {
+        errors.push(
+            ParseError::new(
+                ErrorCode::InvalidEscape,
+                span,
+                "Escaped bytes do not form valid UTF-8",
+            )
+            .with_note(
+                "Multi-byte characters must be escaped as a whole, e.g. `%C3%A4` or `%u00E4`",
+            ),
+        );
+        String::from_utf8_lossy(e.as_bytes()).into_owned()
+    })
 }
 
-pub fn handle_quoted_string(pair: Pair<Rule>) -> StringToken {
-    debug_assert!(pair.as_rule() == Rule::QuotedString);
-    let span = convert_span(pair.as_span());
-    let quoted = pair.as_str();
-    StringToken {
-        value: quoted[1..quoted.len() - 1].to_string(),
-        span,
+enum Escape {
+    Byte(u8),
+    Char(char),
+    /// A `%` which does not start an escape
+    Stray(String),
+}
+
+/// Decodes the escape after a `%`, returning the length of the escape without the `%`.
+fn decode_escape(rest: &str) -> (usize, Result<Escape, String>) {
+    let hex_len = |s: &str, max: usize| {
+        s.bytes()
+            .take(max)
+            .take_while(u8::is_ascii_hexdigit)
+            .count()
+    };
+    let code_point = |digits: &str, len: usize| {
+        let value = u32::from_str_radix(digits, 16).unwrap_or(u32::MAX);
+        char::from_u32(value).map(Escape::Char).ok_or_else(|| {
+            format!(
+                "Escape `%{}` is not a valid Unicode code point",
+                &rest[..len]
+            )
+        })
+    };
+    if let Some(braced) = rest.strip_prefix("u{") {
+        let digits = hex_len(braced, 7);
+        if braced[digits..].starts_with('}') && (1..=6).contains(&digits) {
+            return (digits + 3, code_point(&braced[..digits], digits + 3));
+        }
+        return (
+            digits + 2,
+            Err("Escape `%u{...}` must have 1 to 6 hex digits followed by `}`".to_string()),
+        );
+    }
+    if let Some(digits) = rest.strip_prefix('u') {
+        let len = hex_len(digits, 4);
+        if len < 4 {
+            return (
+                len + 1,
+                Err("Escape `%u` must be followed by 4 hex digits or `{...}`".to_string()),
+            );
+        }
+        return (5, code_point(&digits[..4], 5));
+    }
+    let len = hex_len(rest, 2);
+    if len < 2 {
+        let invalid: String = rest.chars().take(2).take_while(|c| *c != '%').collect();
+        return (
+            invalid.len(),
+            Ok(Escape::Stray(format!(
+                "`%{}` is not an escape and is kept as is (write `%25` for `%`)",
+                invalid
+            ))),
+        );
     }
+    let byte = u8::from_str_radix(&rest[..2], 16).unwrap_or_default();
+    (2, Ok(Escape::Byte(byte)))
 }
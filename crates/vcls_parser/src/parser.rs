@@ -0,0 +1,388 @@
+//! Cursor over the tokens of `lexer`, shared by the recursive-descent functions of every module.
+//!
+//! Spans follow the conventions of the pest grammar this parser replaced: whitespace and
+//! comments between two parts of a construct belong to the construct. So a construct whose
+//! last part is optional and absent also covers the trivia after it, e.g. the span of
+//! `req.http.host` in `req.http.host == "a"` ends right before `==`.
+
+use std::fmt;
+
+use vcls_ast::{Ident, Span, Type, Vcl};
+
+use crate::{
+    cst::{SyntaxKind, SyntaxTree, SyntaxTreeBuilder},
+    error::{ErrorCode, ParseError},
+    lexer::{tokenize, Token, TokenKind},
+};
+
+/// A syntax error aborts the construct being parsed; other diagnostics go to `Parser::errors`.
+pub type SyntaxResult<T> = Result<T, ParseError>;
+
+/// Deepest nesting of the tree being built, counting nodes as well as the operators of
+/// expressions, which nest as deep in the AST. Deeper trees could overflow the stack when
+/// they are dropped or walked.
+pub const MAX_DEPTH: usize = 4096;
+
+/// Deepest nesting of nodes parsed recursively, which stays within the 2 MiB stack of a
+/// spawned thread even in debug builds.
+pub const MAX_RECURSION: usize = 128;
+
+const TYPES: [&str; 11] = [
+    "VOID", "ACL", "BACKEND", "BOOL", "INTEGER", "FLOAT", "ID", "IP", "RTIME", "STRING", "TIME",
+];
+
+/// The flavor of VCL to accept.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+pub enum Dialect {
+    /// https://developer.fastly.com/reference/vcl/
+    #[default]
+    Fastly,
+    /// Varnish Cache 4.x, which adds `vcl 4.1;`, `probe`, `new` and its own return actions.
+    /// Fastly's extensions are accepted as well.
+    Varnish,
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fastly => f.write_str("Fastly"),
+            Self::Varnish => f.write_str("Varnish"),
+        }
+    }
+}
+
+pub struct Parser<'a> {
+    src: &'a str,
+    tokens: Vec<Token>,
+    /// Index of the current token
+    cursor: usize,
+    /// Diagnostics which do not prevent building the AST (invalid numbers, unknown properties, ...)
+    pub errors: Vec<ParseError>,
+    /// Replace broken statements of subroutines by `Statement::Invalid` instead of failing.
+    pub recovering: bool,
+    /// Return type of the subroutine being parsed, which decides whether `return` has an
+    /// action or a value.
+    pub return_type: Type,
+    pub dialect: Dialect,
+    /// Nesting of the node being parsed, see `MAX_DEPTH`
+    depth: usize,
+    /// Nodes being parsed recursively, see `MAX_RECURSION`
+    recursion: usize,
+    /// `depth` when the outermost expression being parsed started
+    pub expression_depth: Option<usize>,
+    builder: Option<SyntaxTreeBuilder<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            tokens: tokenize(src),
+            cursor: 0,
+            errors: vec![],
+            recovering: false,
+            return_type: Type::Void,
+            dialect: Dialect::Fastly,
+            depth: 0,
+            recursion: 0,
+            expression_depth: None,
+            builder: None,
+        }
+    }
+
+    /// A parser which starts at `offset`, a token boundary of `src`, and never looks before it.
+    pub fn starting_at(src: &'a str, offset: usize) -> Self {
+        let mut tokens = tokenize(&src[offset..]);
+        for token in &mut tokens {
+            token.span = Span(token.span.0 + offset, token.span.1 + offset);
+        }
+        Self {
+            src,
+            tokens,
+            cursor: 0,
+            errors: vec![],
+            recovering: false,
+            return_type: Type::Void,
+            dialect: Dialect::Fastly,
+            depth: 0,
+            recursion: 0,
+            expression_depth: None,
+            builder: None,
+        }
+    }
+
+    /// A parser which also builds the lossless syntax tree.
+    pub fn lossless(src: &'a str) -> Self {
+        let mut parser = Self::new(src);
+        parser.builder = Some(SyntaxTreeBuilder::with_tokens(src, parser.tokens.clone()));
+        parser
+    }
+
+    /// The syntax tree, which only has the root node unless the parser is `lossless`.
+    pub fn finish(self, vcl: Vcl) -> SyntaxTree {
+        self.builder
+            .unwrap_or_else(|| SyntaxTreeBuilder::with_tokens(self.src, self.tokens))
+            .finish(vcl)
+    }
+
+    pub fn src(&self) -> &'a str {
+        self.src
+    }
+
+    pub fn text(&self, token: Token) -> &'a str {
+        token.text(self.src)
+    }
+
+    /// Byte offset of the current token.
+    pub fn offset(&self) -> usize {
+        self.tokens
+            .get(self.cursor)
+            .map_or(self.src.len(), |t| t.span.0)
+    }
+
+    pub fn skip_trivia(&mut self) {
+        while self.tokens.get(self.cursor).is_some_and(Token::is_trivia) {
+            self.cursor += 1;
+        }
+    }
+
+    /// Like `skip_trivia`, but stops at a `#FASTLY` macro, which is a statement inside bodies.
+    pub fn skip_trivia_to_statement(&mut self) {
+        while self
+            .tokens
+            .get(self.cursor)
+            .is_some_and(|t| t.is_trivia() && t.kind != TokenKind::FastlyMacro)
+        {
+            self.cursor += 1;
+        }
+    }
+
+    /// The current token, which is trivia unless `skip_trivia` was called.
+    pub fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.cursor).copied()
+    }
+
+    /// The token `n` tokens after the current one, without skipping trivia.
+    pub fn peek_nth(&self, n: usize) -> Option<Token> {
+        self.tokens.get(self.cursor + n).copied()
+    }
+
+    /// The `n`-th token from the current one, skipping trivia.
+    pub fn lookahead(&self, n: usize) -> Option<Token> {
+        self.tokens[self.cursor..]
+            .iter()
+            .filter(|t| !t.is_trivia())
+            .nth(n)
+            .copied()
+    }
+
+    pub fn at_eof(&self) -> bool {
+        self.cursor >= self.tokens.len()
+    }
+
+    pub fn at(&self, kind: TokenKind) -> bool {
+        self.peek().is_some_and(|t| t.kind == kind)
+    }
+
+    pub fn at_keyword(&self, keyword: &str) -> bool {
+        self.peek()
+            .is_some_and(|t| t.kind == TokenKind::Ident && self.text(t) == keyword)
+    }
+
+    /// The text of the current token if it is an identifier.
+    pub fn keyword(&self) -> Option<&'a str> {
+        self.peek()
+            .filter(|t| t.kind == TokenKind::Ident)
+            .map(|t| self.text(t))
+    }
+
+    pub fn at_type(&self) -> bool {
+        self.keyword().is_some_and(|k| TYPES.contains(&k))
+    }
+
+    pub fn bump(&mut self) -> Option<Token> {
+        let token = self.peek();
+        if token.is_some() {
+            self.cursor += 1;
+        }
+        token
+    }
+
+    pub fn eat(&mut self, kind: TokenKind) -> Option<Token> {
+        self.peek().filter(|t| t.kind == kind).inspect(|_| {
+            self.cursor += 1;
+        })
+    }
+
+    /// Skips trivia and consumes a token of `kind`, described as `expected` in errors.
+    pub fn expect(&mut self, kind: TokenKind, expected: &str) -> SyntaxResult<Token> {
+        self.skip_trivia();
+        self.eat(kind).ok_or_else(|| self.unexpected(&[expected]))
+    }
+
+    pub fn expect_keyword(&mut self, keyword: &str) -> SyntaxResult<Token> {
+        self.skip_trivia();
+        match self.peek() {
+            Some(token) if self.at_keyword(keyword) => {
+                self.cursor += 1;
+                Ok(token)
+            }
+            _ => Err(self.unexpected(&[keyword])),
+        }
+    }
+
+    /// An identifier without `.property` or `:subfield`.
+    pub fn expect_ident(&mut self) -> SyntaxResult<Ident> {
+        self.skip_trivia();
+        match (self.peek(), self.keyword()) {
+            (Some(token), Some(name)) if !name.contains(['.', ':']) => {
+                self.bump();
+                Ok(Ident::new(name, token.span))
+            }
+            _ => Err(self.unexpected(&["Ident"])),
+        }
+    }
+
+    pub fn expect_type(&mut self) -> SyntaxResult<Type> {
+        self.skip_trivia();
+        match self.keyword() {
+            Some(typ) if TYPES.contains(&typ) => {
+                self.bump();
+                Ok(Type::from_keyword(typ))
+            }
+            _ => Err(self.unexpected(&["Type"])),
+        }
+    }
+
+    /// A syntax error at the current token.
+    pub fn unexpected(&self, expected: &[&str]) -> ParseError {
+        let found = match self.peek() {
+            None => "end of input".to_string(),
+            Some(t) if t.kind == TokenKind::Unknown && t.span.1 - t.span.0 > 1 => {
+                if self.text(t).starts_with("/*") {
+                    "unterminated comment".to_string()
+                } else {
+                    "unterminated string".to_string()
+                }
+            }
+            Some(t) => format!("`{}`", self.text(t)),
+        };
+        let offset = self.offset();
+        ParseError::new(
+            ErrorCode::Syntax,
+            Span(offset, offset),
+            format!("Expected {}, found {}", expected.join(" or "), found),
+        )
+        .with_expected(expected.iter().copied())
+    }
+
+    /// Reports `construct` at `span` unless the parser accepts `dialect`.
+    pub fn require_dialect(&mut self, dialect: Dialect, span: Span, construct: &str) {
+        if self.dialect != dialect {
+            self.errors.push(
+                ParseError::new(
+                    ErrorCode::UnsupportedInDialect,
+                    span,
+                    format!("{} is only supported in the {} dialect", construct, dialect),
+                )
+                .with_note(format!("the source is parsed as {} VCL", self.dialect)),
+            );
+        }
+    }
+
+    pub fn checkpoint(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn restore(&mut self, checkpoint: usize) {
+        self.cursor = checkpoint;
+    }
+
+    /// Parses `X*` after another part of a construct. The trivia before the first `X` is
+    /// consumed even if there is none, the trivia after the last one is not.
+    pub fn repeat<T>(
+        &mut self,
+        at: impl Fn(&Self) -> bool,
+        mut parse: impl FnMut(&mut Self) -> SyntaxResult<T>,
+    ) -> SyntaxResult<Vec<T>> {
+        let mut items = vec![];
+        self.skip_trivia();
+        if !at(self) {
+            return Ok(items);
+        }
+        items.push(parse(self)?);
+        loop {
+            let checkpoint = self.checkpoint();
+            self.skip_trivia();
+            if !at(self) {
+                self.restore(checkpoint);
+                return Ok(items);
+            }
+            items.push(parse(self)?);
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Counts `levels` more levels of nesting, failing beyond `MAX_DEPTH`.
+    pub fn descend(&mut self, levels: usize) -> SyntaxResult<()> {
+        if self.depth + levels > MAX_DEPTH {
+            return Err(self.too_deep(MAX_DEPTH));
+        }
+        self.depth += levels;
+        Ok(())
+    }
+
+    fn too_deep(&self, max: usize) -> ParseError {
+        let offset = self.offset();
+        ParseError::new(
+            ErrorCode::TooDeep,
+            Span(offset, offset),
+            "Nesting is too deep",
+        )
+        .with_note(format!("at most {} levels are supported", max))
+    }
+
+    /// Forgets the levels counted since `depth`.
+    pub fn ascend_to(&mut self, depth: usize) {
+        self.depth = depth;
+    }
+
+    /// Starts a node of the syntax tree, which `finish_node` closes. Unlike `node`, this
+    /// allows nesting nodes without recursion.
+    pub fn start_node(&mut self, kind: SyntaxKind) -> SyntaxResult<()> {
+        self.descend(1)?;
+        let start = self.offset();
+        if let Some(builder) = &mut self.builder {
+            builder.start_node(kind, start);
+        }
+        Ok(())
+    }
+
+    pub fn finish_node(&mut self) {
+        self.depth -= 1;
+        let end = self.offset();
+        if let Some(builder) = &mut self.builder {
+            builder.finish_node(end);
+        }
+    }
+
+    /// Runs `parse` as a node of the syntax tree.
+    pub fn node<T>(
+        &mut self,
+        kind: SyntaxKind,
+        parse: impl FnOnce(&mut Self) -> SyntaxResult<T>,
+    ) -> SyntaxResult<T> {
+        if self.recursion >= MAX_RECURSION {
+            return Err(self.too_deep(MAX_RECURSION));
+        }
+        self.start_node(kind)?;
+        self.recursion += 1;
+        let result = parse(self);
+        self.recursion -= 1;
+        self.finish_node();
+        result
+    }
+}
@@ -0,0 +1,168 @@
+use vcls_ast::{Declaration, InvalidDeclaration, InvalidStatement, Span, Statement, Vcl};
+
+use crate::{
+    cst::SyntaxKind,
+    declaration::{self, KEYWORDS},
+    error::{ErrorCode, ParseError},
+    lexer::TokenKind,
+    parser::{Dialect, Parser, SyntaxResult},
+    statement,
+};
+
+const ELSE_KEYWORDS: [&str; 4] = ["else", "elif", "elsif", "elseif"];
+
+/// Parses the whole source, skipping over broken declarations and statements.
+///
+/// Broken top-level constructs are replaced by `Declaration::Invalid`, and broken statements
+/// directly inside a subroutine by `Statement::Invalid`, so the rest of the file is still available.
+pub fn parse(src: &str, dialect: Dialect) -> (Vcl, Vec<ParseError>) {
+    let mut p = Parser::new(src);
+    p.recovering = true;
+    p.dialect = dialect;
+    let mut declarations = vec![];
+    while let Some(declaration) = next_declaration(&mut p) {
+        declarations.push(declaration);
+    }
+    let vcl = Vcl { declarations };
+    p.errors.extend(declaration::table::check(&vcl));
+    (vcl, p.errors)
+}
+
+/// Parses the next declaration of a recovering parser, or returns `None` at the end of input.
+pub fn next_declaration(p: &mut Parser) -> Option<Declaration> {
+    p.skip_trivia();
+    if p.at_eof() {
+        return None;
+    }
+    let start = p.offset();
+    let checkpoint = p.checkpoint();
+    let errors = p.errors.len();
+    let declaration = match declaration::parse(p) {
+        // Diagnostics of a declaration which could be parsed, such as an unknown property,
+        // leave it in place.
+        Ok(declaration) => declaration,
+        Err(error) => {
+            p.errors.truncate(errors);
+            p.restore(checkpoint);
+            // The broken declaration reaches at least to where it broke, e.g. past the `acl`
+            // taken for the name in `sub acl *`, so that its diagnostic is inside it.
+            sync_declaration(p);
+            while p.offset() < error.span.0 {
+                sync_declaration(p);
+            }
+            p.errors.push(error);
+            Declaration::Invalid(InvalidDeclaration {
+                span: Span(start, p.offset()),
+            })
+        }
+    };
+    Some(declaration)
+}
+
+/// `{ statement* }` of a subroutine, replacing broken statements by `Statement::Invalid`.
+///
+/// A body which runs into the next declaration or the end of input is closed there.
+pub fn parse_sub_body(p: &mut Parser) -> SyntaxResult<Vec<Statement>> {
+    p.node(SyntaxKind::Block, |p| {
+        let open = p.expect(TokenKind::LBrace, "{")?.span;
+        let mut body = vec![];
+        loop {
+            let end = p.checkpoint();
+            p.skip_trivia_to_statement();
+            if p.eat(TokenKind::RBrace).is_some() {
+                return Ok(body);
+            }
+            let start = p.offset();
+            let checkpoint = p.checkpoint();
+            let errors = p.errors.len();
+            match statement::parse(p) {
+                Ok(statement) => body.push(statement),
+                Err(error) => {
+                    p.errors.truncate(errors);
+                    p.restore(checkpoint);
+                    if p.at_eof() || at_line_start_keyword(p) {
+                        p.errors.push(
+                            ParseError::new(ErrorCode::Syntax, open, "Unclosed subroutine body")
+                                .with_expected(["}"])
+                                .with_related(Span(start, start), "body ends here"),
+                        );
+                        p.restore(end);
+                        return Ok(body);
+                    }
+                    p.errors.push(error);
+                    sync_statement(p);
+                    body.push(Statement::Invalid(InvalidStatement {
+                        span: Span(start, p.offset()),
+                    }));
+                }
+            }
+        }
+    })
+}
+
+/// A declaration keyword at the start of a line, which most likely begins a new declaration.
+fn at_line_start_keyword(p: &Parser) -> bool {
+    let offset = p.offset();
+    let before = &p.src()[..offset];
+    (before.is_empty() || before.ends_with('\n'))
+        && p.keyword().is_some_and(|k| KEYWORDS.contains(&k))
+}
+
+/// Skips to the next declaration keyword.
+/// Inside braces only keywords at the start of a line are considered, to get out of unclosed bodies.
+fn sync_declaration(p: &mut Parser) {
+    let mut depth = 0usize;
+    p.bump();
+    while let Some(token) = p.peek() {
+        match token.kind {
+            TokenKind::LBrace => depth += 1,
+            TokenKind::RBrace => depth = depth.saturating_sub(1),
+            TokenKind::Ident
+                if depth == 0 && p.keyword().is_some_and(|k| KEYWORDS.contains(&k)) =>
+            {
+                return
+            }
+            TokenKind::Ident if at_line_start_keyword(p) => return,
+            _ => {}
+        }
+        p.bump();
+    }
+}
+
+/// Skips past the next `;` or balanced `{ ... }` block, stopping before the `}` closing the
+/// enclosing body.
+fn sync_statement(p: &mut Parser) {
+    let start = p.checkpoint();
+    let mut depth = 0usize;
+    while let Some(token) = p.peek() {
+        match token.kind {
+            TokenKind::LBrace => depth += 1,
+            TokenKind::RBrace if depth == 0 => {
+                if p.checkpoint() == start {
+                    p.bump();
+                }
+                return;
+            }
+            TokenKind::RBrace => {
+                depth -= 1;
+                if depth == 0 && !at_else(p) {
+                    p.bump();
+                    return;
+                }
+            }
+            TokenKind::Semicolon if depth == 0 => {
+                p.bump();
+                return;
+            }
+            TokenKind::Ident if p.checkpoint() > start && at_line_start_keyword(p) => return,
+            _ => {}
+        }
+        p.bump();
+    }
+}
+
+/// Whether the token after the current `}` continues an `if` statement.
+fn at_else(p: &Parser) -> bool {
+    p.lookahead(1)
+        .is_some_and(|t| t.kind == TokenKind::Ident && ELSE_KEYWORDS.contains(&p.text(t)))
+}
@@ -1,17 +1,23 @@
-use pest::iterators::Pair;
-use vcls_ast::AddStatement;
+use vcls_ast::{AddStatement, Span};
 
-use crate::{expression, utils::convert_span, variable, ParseResult, Rule};
+use crate::{
+    expression,
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+    variable,
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<AddStatement> {
-    debug_assert!(pair.as_rule() == Rule::AddStatement);
-    let span = convert_span(pair.as_span());
-    let mut inner = pair.into_inner();
-    let target = variable::handle(inner.find(|p| p.as_rule() == Rule::Variable).unwrap())?;
-    let value = expression::handle(inner.find(|p| p.as_rule() == Rule::Expr).unwrap())?;
+pub fn parse(p: &mut Parser) -> SyntaxResult<AddStatement> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword("add")?;
+    let target = variable::parse(p)?;
+    p.expect(TokenKind::Assign, "=")?;
+    let value = expression::parse(p)?;
+    p.expect(TokenKind::Semicolon, ";")?;
     Ok(AddStatement {
         target,
         value,
-        span,
+        span: Span(start, p.offset()),
     })
 }
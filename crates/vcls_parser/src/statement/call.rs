@@ -1,20 +1,24 @@
-use pest::iterators::Pair;
-use vcls_ast::{CallStatement, Variable};
+use vcls_ast::{CallStatement, Span, Variable};
 
-use crate::{utils::convert_span, ParseResult, Rule};
+use crate::{
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<CallStatement> {
-    debug_assert!(pair.as_rule() == Rule::CallStatement);
-    let span = convert_span(pair.as_span());
-    let mut inner = pair.into_inner();
-    let target = inner.find(|p| p.as_rule() == Rule::Ident).unwrap();
+pub fn parse(p: &mut Parser) -> SyntaxResult<CallStatement> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword("call")?;
+    let name = p.expect_ident()?;
+    let target = Variable {
+        span: name.span,
+        name: name.name,
+        properties: vec![],
+        sub_field: None,
+    };
+    p.expect(TokenKind::Semicolon, ";")?;
     Ok(CallStatement {
-        target: Variable {
-            name: target.as_str().to_string(),
-            properties: vec![],
-            sub_field: None,
-            span: convert_span(target.as_span()),
-        },
-        span,
+        target,
+        span: Span(start, p.offset()),
     })
 }
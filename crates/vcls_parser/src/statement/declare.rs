@@ -1,13 +1,22 @@
-use pest::iterators::Pair;
-use vcls_ast::{DeclareStatement, Type};
+use vcls_ast::{DeclareStatement, Span};
 
-use crate::{utils::convert_span, variable, ParseResult, Rule};
+use crate::{
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+    variable,
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<DeclareStatement> {
-    debug_assert!(pair.as_rule() == Rule::DeclareStatement);
-    let span = convert_span(pair.as_span());
-    let mut inner = pair.into_inner();
-    let target = variable::handle(inner.find(|p| p.as_rule() == Rule::Variable).unwrap())?;
-    let typ = Type::from_keyword(inner.find(|p| p.as_rule() == Rule::Type).unwrap().as_str());
-    Ok(DeclareStatement { target, typ, span })
+pub fn parse(p: &mut Parser) -> SyntaxResult<DeclareStatement> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword("declare")?;
+    p.expect_keyword("local")?;
+    let target = variable::parse(p)?;
+    let typ = p.expect_type()?;
+    p.expect(TokenKind::Semicolon, ";")?;
+    Ok(DeclareStatement {
+        target,
+        typ,
+        span: Span(start, p.offset()),
+    })
 }
@@ -1,32 +1,30 @@
-use pest::iterators::Pair;
-use vcls_ast::ErrorStatement;
+use vcls_ast::{ErrorStatement, Span};
 
-use crate::{expression, utils::convert_span, ParseResult, Rule};
+use crate::{
+    expression,
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<ErrorStatement> {
-    debug_assert!(pair.as_rule() == Rule::ErrorStatement);
-    let span = convert_span(pair.as_span());
-    let mut inner = pair.into_inner();
-    let info = inner.find(|p| p.as_rule() == Rule::ErrorInfo);
-    if let Some(p) = info {
-        let mut inner = p.into_inner();
-        let status = Some(expression::handle(
-            inner.find(|p| p.as_rule() == Rule::Expr).unwrap(),
-        )?);
-        let message = inner
-            .find(|p| p.as_rule() == Rule::Expr)
-            .map(|p| expression::handle(p))
-            .transpose()?;
-        Ok(ErrorStatement {
-            status,
-            message,
-            span,
-        })
-    } else {
-        Ok(ErrorStatement {
-            status: None,
-            message: None,
-            span,
-        })
+/// `error;`, `error status;` or `error status message;`
+pub fn parse(p: &mut Parser) -> SyntaxResult<ErrorStatement> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword("error")?;
+    p.skip_trivia();
+    let mut status = None;
+    let mut message = None;
+    if !p.at(TokenKind::Semicolon) {
+        status = Some(expression::parse(p)?);
+        p.skip_trivia();
+        if !p.at(TokenKind::Semicolon) {
+            message = Some(expression::parse(p)?);
+        }
     }
+    p.expect(TokenKind::Semicolon, ";")?;
+    Ok(ErrorStatement {
+        status,
+        message,
+        span: Span(start, p.offset()),
+    })
 }
@@ -0,0 +1,107 @@
+use vcls_ast::{ElseStatement, GotoStatement, LabelStatement, Span, Statement};
+
+use crate::{
+    error::{ErrorCode, ParseError},
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+};
+
+/// `name:`
+pub fn at_label(p: &Parser) -> bool {
+    p.keyword().is_some_and(|k| !k.contains(['.', ':']))
+        && p.lookahead(1).is_some_and(|t| t.kind == TokenKind::Colon)
+}
+
+pub fn parse(p: &mut Parser) -> SyntaxResult<GotoStatement> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword("goto")?;
+    let label = p.expect_ident()?;
+    p.expect(TokenKind::Semicolon, ";")?;
+    Ok(GotoStatement {
+        label,
+        span: Span(start, p.offset()),
+    })
+}
+
+pub fn parse_label(p: &mut Parser) -> SyntaxResult<LabelStatement> {
+    p.skip_trivia();
+    let start = p.offset();
+    let name = p.expect_ident()?;
+    p.expect(TokenKind::Colon, ":")?;
+    Ok(LabelStatement {
+        name,
+        span: Span(start, p.offset()),
+    })
+}
+
+/// Checks that every `goto` of a subroutine body jumps forward to a label of the same body,
+/// and that no label is defined twice.
+pub fn validate(body: &[Statement]) -> Vec<ParseError> {
+    let mut labels = vec![];
+    let mut gotos = vec![];
+    collect(body, &mut labels, &mut gotos);
+    let mut errors = vec![];
+    for (i, label) in labels.iter().enumerate() {
+        if let Some(first) = labels[..i].iter().find(|l| l.name.name == label.name.name) {
+            errors.push(
+                ParseError::new(
+                    ErrorCode::DuplicateLabel,
+                    label.span,
+                    format!("Label `{}` is already defined", label.name),
+                )
+                .with_related(first.span, "first defined here"),
+            );
+        }
+    }
+    for goto in gotos {
+        let mut targets = labels
+            .iter()
+            .filter(|l| l.name.name == goto.label.name)
+            .peekable();
+        match targets.peek() {
+            None => errors.push(ParseError::new(
+                ErrorCode::UnknownLabel,
+                goto.label.span,
+                format!("Label `{}` is not defined in this subroutine", goto.label),
+            )),
+            Some(label) if label.span.0 < goto.span.0 => errors.push(
+                ParseError::new(
+                    ErrorCode::BackwardGoto,
+                    goto.span,
+                    format!("`goto {}` jumps backward", goto.label),
+                )
+                .with_related(label.span, "label defined here")
+                .with_note("goto can only jump forward"),
+            ),
+            Some(_) => {}
+        }
+    }
+    errors
+}
+
+/// Labels and gotos in source order, including those in nested blocks.
+fn collect<'a>(
+    body: &'a [Statement],
+    labels: &mut Vec<&'a LabelStatement>,
+    gotos: &mut Vec<&'a GotoStatement>,
+) {
+    for statement in body {
+        match statement {
+            Statement::Label(label) => labels.push(label),
+            Statement::Goto(goto) => gotos.push(goto),
+            Statement::If(s) => {
+                let mut s = s;
+                loop {
+                    collect(&s.body, labels, gotos);
+                    match &s.els {
+                        Some(ElseStatement::If(els)) => s = els,
+                        Some(ElseStatement::Body(body)) => break collect(body, labels, gotos),
+                        None => break,
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
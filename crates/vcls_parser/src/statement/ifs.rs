@@ -1,61 +1,100 @@
-use pest::iterators::Pair;
-use vcls_ast::{ElseStatement, IfStatement, Statement};
+use vcls_ast::{ElseStatement, Expression, IfStatement, Span, Statement};
 
-use crate::{expression, statement, utils::convert_span, ParseResult, Rule};
+use crate::{
+    cst::SyntaxKind,
+    expression,
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+    statement,
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<IfStatement> {
-    debug_assert!(pair.as_rule() == Rule::IfStatement || pair.as_rule() == Rule::ElseIf);
-    let span = convert_span(pair.as_span());
-    let mut inner = pair.into_inner();
-    let condition = expression::handle(inner.find(|p| p.as_rule() == Rule::Expr).unwrap())?;
-    let body = handle_body(inner.find(|p| p.as_rule() == Rule::IfBody).unwrap())?;
-    let els = inner
-        .find(|p| p.as_rule() == Rule::IfElse)
-        .map(handle_else)
-        .unwrap_or(Ok(None))?;
+const ELSE_IF_KEYWORDS: [&str; 3] = ["elif", "elsif", "elseif"];
+
+pub fn parse(p: &mut Parser) -> SyntaxResult<IfStatement> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword("if")?;
+    parse_rest(p, start)
+}
+
+/// Whether an `else` clause follows, looking past trivia.
+pub fn at_else(p: &Parser) -> bool {
+    p.lookahead(0).is_some_and(|t| {
+        t.kind == TokenKind::Ident && (p.text(t) == "else" || ELSE_IF_KEYWORDS.contains(&p.text(t)))
+    })
+}
+
+/// `(condition) { ... }` and an optional `else` clause.
+///
+/// `else if` clauses are parsed in a loop rather than recursively, so that long chains of
+/// them do not exhaust the stack; their nodes are still nested in the syntax tree.
+fn parse_rest(p: &mut Parser, start: usize) -> SyntaxResult<IfStatement> {
+    let (condition, body) = parse_branch(p)?;
+    let mut else_ifs = vec![];
+    let mut nodes = 0;
+    let els = parse_else_chain(p, &mut else_ifs, &mut nodes);
+    for _ in 0..nodes {
+        p.finish_node();
+    }
+    let end = p.offset();
+    let mut els = els?;
+    while let Some((start, condition, body)) = else_ifs.pop() {
+        els = Some(ElseStatement::If(Box::new(IfStatement {
+            condition,
+            body,
+            els,
+            span: Span(start, end),
+        })));
+    }
     Ok(IfStatement {
         condition,
         body,
         els,
-        span,
+        span: Span(start, end),
     })
 }
 
-fn handle_else(pair: Pair<Rule>) -> ParseResult<Option<ElseStatement>> {
-    let inner = pair.into_inner();
-    for pair in inner {
-        match pair.as_rule() {
-            Rule::Else => {
-                return Ok(Some(ElseStatement::Body(handle_body(
-                    pair.into_inner()
-                        .find(|p| p.as_rule() == Rule::IfBody)
-                        .unwrap(),
-                )?)));
-            }
-            Rule::ElseIf => {
-                return Ok(Some(ElseStatement::If(Box::new(handle(pair)?))));
-            }
-            Rule::COMMENT => {}
-            _ => unreachable!("Unexpected rule: {:?}", pair.as_rule()),
-        }
-    }
-    Ok(None)
+/// `(condition) { ... }`
+fn parse_branch(p: &mut Parser) -> SyntaxResult<(Expression, Vec<Statement>)> {
+    p.expect(TokenKind::LParen, "(")?;
+    let condition = expression::parse(p)?;
+    p.expect(TokenKind::RParen, ")")?;
+    let body = statement::parse_block(p)?;
+    Ok((condition, body))
 }
 
-fn handle_body(pair: Pair<Rule>) -> ParseResult<Vec<Statement>> {
-    debug_assert!(pair.as_rule() == Rule::IfBody);
-    let inner = pair.into_inner();
-    let mut statements = vec![];
-    let mut errors = vec![];
-    for pair in inner {
-        match pair.as_rule() {
-            Rule::Statement => match statement::handle(pair) {
-                Ok(s) => statements.push(s),
-                Err(e) => errors.extend(e),
-            },
-            Rule::COMMENT => {}
-            _ => unreachable!("Unexpected rule: {:?}", pair.as_rule()),
+/// Pushes the start, condition and body of each `else if` clause to `else_ifs` and returns
+/// the final `else` clause, counting the nodes it started in `nodes`.
+fn parse_else_chain(
+    p: &mut Parser,
+    else_ifs: &mut Vec<(usize, Expression, Vec<Statement>)>,
+    nodes: &mut usize,
+) -> SyntaxResult<Option<ElseStatement>> {
+    loop {
+        p.skip_trivia();
+        if !at_else(p) {
+            return Ok(None);
+        }
+        p.start_node(SyntaxKind::ElseClause)?;
+        *nodes += 1;
+        let is_else_if = p.keyword().is_some_and(|k| ELSE_IF_KEYWORDS.contains(&k))
+            || (p.at_keyword("else")
+                && p.lookahead(1)
+                    .is_some_and(|t| t.kind == TokenKind::Ident && p.text(t) == "if"));
+        if !is_else_if {
+            p.expect_keyword("else")?;
+            return statement::parse_block(p).map(|body| Some(ElseStatement::Body(body)));
+        }
+        p.start_node(SyntaxKind::IfStatement)?;
+        *nodes += 1;
+        let start = p.offset();
+        if p.at_keyword("else") {
+            p.bump();
+            p.expect_keyword("if")?;
+        } else {
+            p.bump();
         }
+        let (condition, body) = parse_branch(p)?;
+        else_ifs.push((start, condition, body));
     }
-    Ok(statements)
 }
@@ -1,12 +1,19 @@
-use pest::iterators::Pair;
-use vcls_ast::LogStatement;
+use vcls_ast::{LogStatement, Span};
 
-use crate::{expression, utils::convert_span, ParseResult, Rule};
+use crate::{
+    expression,
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<LogStatement> {
-    debug_assert!(pair.as_rule() == Rule::LogStatement);
-    let span = convert_span(pair.as_span());
-    let mut inner = pair.into_inner();
-    let message = expression::handle(inner.find(|p| p.as_rule() == Rule::Expr).unwrap())?;
-    Ok(LogStatement { message, span })
+pub fn parse(p: &mut Parser) -> SyntaxResult<LogStatement> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword("log")?;
+    let message = expression::parse(p)?;
+    p.expect(TokenKind::Semicolon, ";")?;
+    Ok(LogStatement {
+        message,
+        span: Span(start, p.offset()),
+    })
 }
@@ -1,51 +1,145 @@
-use pest::iterators::Pair;
-use vcls_ast::{EsiStatement, RestartStatement, Statement};
+use vcls_ast::{EsiStatement, FastlyMacroStatement, Ident, RestartStatement, Span, Statement};
 
-use crate::{utils::convert_span, ParseResult, Rule};
+use crate::{
+    cst::SyntaxKind,
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+};
 
 mod add;
 mod call;
 mod declare;
 mod error;
+pub mod goto;
 mod ifs;
 mod include;
 mod log;
+mod new;
 mod ret;
 mod set;
 mod synthetic;
 mod unset;
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<Statement> {
-    debug_assert!(pair.as_rule() == Rule::Statement);
-    let inner = pair.into_inner();
-    for pair in inner {
-        match pair.as_rule() {
-            Rule::IfStatement => return ifs::handle(pair).map(Statement::If),
-            Rule::SetStatement => return set::handle(pair).map(Statement::Set),
-            Rule::UnsetStatement => return unset::handle(pair).map(Statement::Unset),
-            Rule::AddStatement => return add::handle(pair).map(Statement::Add),
-            Rule::CallStatement => return call::handle(pair).map(Statement::Call),
-            Rule::DeclareStatement => return declare::handle(pair).map(Statement::Declare),
-            Rule::ErrorStatement => return error::handle(pair).map(Statement::Error),
-            Rule::EsiStatement => {
-                return Ok(Statement::Esi(EsiStatement {
-                    span: convert_span(pair.as_span()),
-                }))
-            }
-            Rule::IncludeStatement => return include::handle(pair).map(Statement::Include),
-            Rule::LogStatement => return log::handle(pair).map(Statement::Log),
-            Rule::RestartStatement => {
-                return Ok(Statement::Restart(RestartStatement {
-                    span: convert_span(pair.as_span()),
-                }))
+const KEYWORDS: [&str; 17] = [
+    "if",
+    "set",
+    "unset",
+    "remove",
+    "add",
+    "call",
+    "declare",
+    "error",
+    "esi",
+    "goto",
+    "include",
+    "log",
+    "new",
+    "restart",
+    "return",
+    "synthetic",
+    "synthetic.base64",
+];
+
+pub fn at_statement(p: &Parser) -> bool {
+    p.at(TokenKind::FastlyMacro)
+        || p.keyword().is_some_and(|k| KEYWORDS.contains(&k))
+        || goto::at_label(p)
+}
+
+pub fn parse(p: &mut Parser) -> SyntaxResult<Statement> {
+    p.skip_trivia_to_statement();
+    if let Some(token) = p.peek().filter(|t| t.kind == TokenKind::FastlyMacro) {
+        return p.node(SyntaxKind::FastlyMacroStatement, |p| {
+            p.bump();
+            let rest = &p.text(token)["#FASTLY".len()..];
+            let name = rest.trim();
+            let start = token.span.0 + "#FASTLY".len() + (rest.len() - rest.trim_start().len());
+            Ok(Statement::FastlyMacro(FastlyMacroStatement {
+                span: token.span,
+                name: Ident::new(name, Span(start, start + name.len())),
+            }))
+        });
+    }
+    if goto::at_label(p) {
+        return p
+            .node(SyntaxKind::LabelStatement, goto::parse_label)
+            .map(Statement::Label);
+    }
+    match p.keyword() {
+        Some("if") => p
+            .node(SyntaxKind::IfStatement, ifs::parse)
+            .map(Statement::If),
+        Some("set") => p
+            .node(SyntaxKind::SetStatement, set::parse)
+            .map(Statement::Set),
+        Some("unset" | "remove") => p
+            .node(SyntaxKind::UnsetStatement, unset::parse)
+            .map(Statement::Unset),
+        Some("add") => p
+            .node(SyntaxKind::AddStatement, add::parse)
+            .map(Statement::Add),
+        Some("call") => p
+            .node(SyntaxKind::CallStatement, call::parse)
+            .map(Statement::Call),
+        Some("declare") => p
+            .node(SyntaxKind::DeclareStatement, declare::parse)
+            .map(Statement::Declare),
+        Some("error") => p
+            .node(SyntaxKind::ErrorStatement, error::parse)
+            .map(Statement::Error),
+        Some("esi") => p.node(SyntaxKind::EsiStatement, |p| {
+            let span = parse_keyword_statement(p, "esi")?;
+            Ok(Statement::Esi(EsiStatement { span }))
+        }),
+        Some("goto") => p
+            .node(SyntaxKind::GotoStatement, goto::parse)
+            .map(Statement::Goto),
+        Some("include") => p
+            .node(SyntaxKind::IncludeStatement, include::parse)
+            .map(Statement::Include),
+        Some("log") => p
+            .node(SyntaxKind::LogStatement, log::parse)
+            .map(Statement::Log),
+        Some("new") => p
+            .node(SyntaxKind::NewStatement, new::parse)
+            .map(Statement::New),
+        Some("restart") => p.node(SyntaxKind::RestartStatement, |p| {
+            let span = parse_keyword_statement(p, "restart")?;
+            Ok(Statement::Restart(RestartStatement { span }))
+        }),
+        Some("return") => p
+            .node(SyntaxKind::ReturnStatement, ret::parse)
+            .map(Statement::Return),
+        Some("synthetic" | "synthetic.base64") => p
+            .node(SyntaxKind::SyntheticStatement, synthetic::parse)
+            .map(Statement::Synthetic),
+        _ => Err(p.unexpected(&["Statement"])),
+    }
+}
+
+/// `{ statement* }`
+pub fn parse_block(p: &mut Parser) -> SyntaxResult<Vec<Statement>> {
+    p.node(SyntaxKind::Block, |p| {
+        p.expect(TokenKind::LBrace, "{")?;
+        let mut statements = vec![];
+        loop {
+            p.skip_trivia_to_statement();
+            if p.eat(TokenKind::RBrace).is_some() {
+                return Ok(statements);
             }
-            Rule::ReturnStatement => return ret::handle(pair).map(Statement::Return),
-            Rule::SyntheticStatement | Rule::SyntheticBase64Statement => {
-                return synthetic::handle(pair).map(Statement::Synthetic)
+            if !at_statement(p) {
+                return Err(p.unexpected(&["Statement", "}"]));
             }
-            Rule::COMMENT => {}
-            _ => unreachable!("Unexpected token: {:?}", pair.as_rule()),
+            statements.push(parse(p)?);
         }
-    }
-    Err(vec![]) // unreachable!()
+    })
+}
+
+/// `keyword;`
+fn parse_keyword_statement(p: &mut Parser, keyword: &str) -> SyntaxResult<Span> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword(keyword)?;
+    p.expect(TokenKind::Semicolon, ";")?;
+    Ok(Span(start, p.offset()))
 }
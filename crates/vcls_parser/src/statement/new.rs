@@ -0,0 +1,25 @@
+use vcls_ast::{NewStatement, Span};
+
+use crate::{
+    cst::SyntaxKind,
+    expression,
+    lexer::TokenKind,
+    parser::{Dialect, Parser, SyntaxResult},
+};
+
+pub fn parse(p: &mut Parser) -> SyntaxResult<NewStatement> {
+    p.skip_trivia();
+    let start = p.offset();
+    let keyword = p.expect_keyword("new")?;
+    let name = p.expect_ident()?;
+    p.expect(TokenKind::Assign, "=")?;
+    p.skip_trivia();
+    let constructor = p.node(SyntaxKind::CallExpression, expression::parse_call)?;
+    p.expect(TokenKind::Semicolon, ";")?;
+    p.require_dialect(Dialect::Varnish, keyword.span, "`new` statement");
+    Ok(NewStatement {
+        name,
+        constructor,
+        span: Span(start, p.offset()),
+    })
+}
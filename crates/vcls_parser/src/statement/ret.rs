@@ -1,24 +1,89 @@
-use pest::iterators::Pair;
-use vcls_ast::ReturnStatement;
+use vcls_ast::{ReturnAction, ReturnStatement, ReturnValue, Span, Type};
 
-use crate::{expression, utils::convert_span, ParseResult, Rule};
+use crate::{
+    cst::SyntaxKind,
+    error::{ErrorCode, ParseError},
+    expression,
+    lexer::TokenKind,
+    parser::{Dialect, Parser, SyntaxResult},
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<ReturnStatement> {
-    debug_assert!(pair.as_rule() == Rule::ReturnStatement);
-    let span = convert_span(pair.as_span());
-    let inner = pair.into_inner();
-    for pair in inner {
-        let span = convert_span(pair.as_span());
-        match pair.as_rule() {
-            Rule::Expr => {
-                return Ok(ReturnStatement {
-                    value: Some(expression::handle(pair)?),
-                    span,
-                })
-            }
-            Rule::COMMENT => {}
-            _ => unreachable!("Unexpected token: {:?}", pair.as_rule()),
+const FASTLY_ACTIONS: [&str; 9] = [
+    "lookup",
+    "pass",
+    "deliver",
+    "deliver_stale",
+    "fetch",
+    "hash",
+    "restart",
+    "error",
+    "upgrade",
+];
+
+const VARNISH_ACTIONS: [&str; 8] = [
+    "pipe", "purge", "synth", "miss", "fail", "abandon", "retry", "ok",
+];
+
+pub fn parse(p: &mut Parser) -> SyntaxResult<ReturnStatement> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword("return")?;
+    p.skip_trivia();
+    if p.eat(TokenKind::Semicolon).is_some() {
+        return Ok(ReturnStatement {
+            value: None,
+            span: Span(start, p.offset()),
+        });
+    }
+    let value_start = p.offset();
+    let value = if p.return_type == Type::Void {
+        ReturnValue::Action(parse_action(p)?)
+    } else {
+        ReturnValue::Expression(expression::parse(p)?)
+    };
+    let span = Span(value_start, p.offset());
+    p.expect(TokenKind::Semicolon, ";")?;
+    Ok(ReturnStatement {
+        value: Some(value),
+        span,
+    })
+}
+
+/// `(pass)`, `pass` or `(synth(404, "Not Found"))`
+fn parse_action(p: &mut Parser) -> SyntaxResult<ReturnAction> {
+    let paren = p.eat(TokenKind::LParen).is_some();
+    p.skip_trivia();
+    let Some(token) = p.peek().filter(|t| t.kind == TokenKind::Ident) else {
+        return Err(p.unexpected(&["ReturnAction"]));
+    };
+    let name = p.text(token);
+    let action = if name == "synth" {
+        let call = p.node(SyntaxKind::CallExpression, expression::parse_call)?;
+        ReturnAction::Synth(call.arguments)
+    } else {
+        p.bump();
+        ReturnAction::from_keyword(name)
+    };
+    let span = Span(token.span.0, p.offset());
+    if VARNISH_ACTIONS.contains(&name) {
+        p.require_dialect(Dialect::Varnish, span, &format!("Return action `{}`", name));
+    } else if let ReturnAction::Unknown(_) = action {
+        let mut expected = FASTLY_ACTIONS.to_vec();
+        if p.dialect == Dialect::Varnish {
+            expected.extend(VARNISH_ACTIONS);
         }
+        p.errors.push(
+            ParseError::new(
+                ErrorCode::UnknownReturnAction,
+                span,
+                format!("Unknown return action: {}", name),
+            )
+            .with_expected(expected),
+        );
+    }
+    if paren {
+        p.expect(TokenKind::RParen, ")")?;
     }
-    Ok(ReturnStatement { value: None, span })
+    p.skip_trivia();
+    Ok(action)
 }
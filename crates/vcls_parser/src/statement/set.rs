@@ -1,31 +1,55 @@
 use std::str::FromStr;
 
-use pest::iterators::Pair;
-use vcls_ast::{SetOperator, SetStatement};
+use vcls_ast::{SetOperator, SetStatement, Span};
 
-use crate::{error::ParseError, expression, utils::convert_span, variable, ParseResult, Rule};
+use crate::{
+    error::{ErrorCode, ParseError},
+    expression,
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+    variable,
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<SetStatement> {
-    debug_assert!(pair.as_rule() == Rule::SetStatement);
-    let span = convert_span(pair.as_span());
-    let mut inner = pair.into_inner();
-    let target = variable::handle(inner.find(|p| p.as_rule() == Rule::Variable).unwrap())?;
-    let operator = SetOperator::from_str(
-        inner
-            .find(|p| p.as_rule() == Rule::SetOperator)
-            .unwrap()
-            .as_str(),
-    )
-    .map_err(|_| {
-        vec![ParseError {
-            message: format!("Invalid set operator: {:?}", inner.as_str()),
-        }]
-    })?;
-    let value = expression::handle(inner.find(|p| p.as_rule() == Rule::Expr).unwrap())?;
+pub fn parse(p: &mut Parser) -> SyntaxResult<SetStatement> {
+    p.skip_trivia();
+    let start = p.offset();
+    p.expect_keyword("set")?;
+    let target = variable::parse(p)?;
+    let operator = parse_operator(p)?;
+    let value = expression::parse(p)?;
+    p.expect(TokenKind::Semicolon, ";")?;
     Ok(SetStatement {
         target,
         operator,
         value,
-        span,
+        span: Span(start, p.offset()),
+    })
+}
+
+/// `=`, `+=`, ..., where `ror=` and `rol=` are an identifier followed by `=`.
+fn parse_operator(p: &mut Parser) -> SyntaxResult<SetOperator> {
+    p.skip_trivia();
+    let start = p.offset();
+    match p.peek().map(|t| t.kind) {
+        Some(TokenKind::Assign | TokenKind::CompoundAssign) => {
+            p.bump();
+        }
+        Some(TokenKind::Ident)
+            if matches!(p.keyword(), Some("ror" | "rol"))
+                && p.peek_nth(1).is_some_and(|t| t.kind == TokenKind::Assign) =>
+        {
+            p.bump();
+            p.bump();
+        }
+        _ => return Err(p.unexpected(&["SetOperator"])),
+    }
+    let span = Span(start, p.offset());
+    let operator = &p.src()[span.0..span.1];
+    SetOperator::from_str(operator).map_err(|_| {
+        ParseError::new(
+            ErrorCode::InvalidSetOperator,
+            span,
+            format!("Invalid set operator: {:?}", operator),
+        )
     })
 }
@@ -1,21 +1,26 @@
-use pest::iterators::Pair;
-use vcls_ast::SyntheticStatement;
+use vcls_ast::{Span, SyntheticStatement};
 
-use crate::{expression, utils::convert_span, ParseResult, Rule};
+use crate::{
+    expression,
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<SyntheticStatement> {
-    let rule = pair.as_rule();
-    debug_assert!(rule == Rule::SyntheticStatement || rule == Rule::SyntheticBase64Statement);
-    let span = convert_span(pair.as_span());
-    let base64 = rule == Rule::SyntheticBase64Statement;
-    let value = expression::handle(
-        pair.into_inner()
-            .find(|p| p.as_rule() == Rule::Expr)
-            .unwrap(),
-    )?;
+/// `synthetic value;` or `synthetic.base64 value;`
+pub fn parse(p: &mut Parser) -> SyntaxResult<SyntheticStatement> {
+    p.skip_trivia();
+    let start = p.offset();
+    let base64 = p.at_keyword("synthetic.base64");
+    p.expect_keyword(if base64 {
+        "synthetic.base64"
+    } else {
+        "synthetic"
+    })?;
+    let value = expression::parse(p)?;
+    p.expect(TokenKind::Semicolon, ";")?;
     Ok(SyntheticStatement {
         value,
         base64,
-        span,
+        span: Span(start, p.offset()),
     })
 }
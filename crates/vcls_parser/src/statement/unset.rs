@@ -1,12 +1,22 @@
-use pest::iterators::Pair;
-use vcls_ast::UnsetStatement;
+use vcls_ast::{Span, UnsetStatement};
 
-use crate::{utils::convert_span, variable, ParseResult, Rule};
+use crate::{
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+    variable,
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<UnsetStatement> {
-    debug_assert!(pair.as_rule() == Rule::UnsetStatement);
-    let span = convert_span(pair.as_span());
-    let mut inner = pair.into_inner();
-    let target = variable::handle(inner.find(|p| p.as_rule() == Rule::Variable).unwrap())?;
-    Ok(UnsetStatement { target, span })
+/// `unset target;` or its alias `remove target;`
+pub fn parse(p: &mut Parser) -> SyntaxResult<UnsetStatement> {
+    p.skip_trivia();
+    let start = p.offset();
+    if p.expect_keyword("unset").is_err() {
+        p.expect_keyword("remove")?;
+    }
+    let target = variable::parse(p)?;
+    p.expect(TokenKind::Semicolon, ";")?;
+    Ok(UnsetStatement {
+        target,
+        span: Span(start, p.offset()),
+    })
 }
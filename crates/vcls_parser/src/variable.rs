@@ -1,33 +1,32 @@
-use pest::iterators::Pair;
-use vcls_ast::Variable;
+use vcls_ast::{Span, Variable};
 
-use crate::{utils::convert_span, ParseResult, Rule};
+use crate::{
+    cst::SyntaxKind,
+    lexer::TokenKind,
+    parser::{Parser, SyntaxResult},
+};
 
-pub fn handle(pair: Pair<Rule>) -> ParseResult<Variable> {
-    debug_assert!(pair.as_rule() == Rule::Variable);
-    let span = convert_span(pair.as_span());
-    let mut inner = pair.into_inner();
-    let name = inner
-        .find(|p| p.as_rule() == Rule::Ident)
-        .unwrap()
-        .as_str()
-        .to_string();
-    let mut properties = vec![];
-    let mut sub_field = None;
-    for pair in inner {
-        match pair.as_rule() {
-            Rule::VariableProp => properties.push(pair.as_str()[1..].to_string()),
-            Rule::SubfieldAccess => {
-                sub_field = Some(pair.as_str()[1..].to_string());
-            }
-            Rule::COMMENT => {}
-            _ => unreachable!("Unexpected rule: {:?}", pair.as_rule()),
+/// `name.property.property:subfield`, which the lexer keeps in one token.
+pub fn parse(p: &mut Parser) -> SyntaxResult<Variable> {
+    p.node(SyntaxKind::Variable, |p| {
+        p.skip_trivia();
+        let start = p.offset();
+        let token = p.expect(TokenKind::Ident, "Variable")?;
+        let (path, sub_field) = match p.text(token).split_once(':') {
+            Some((path, sub_field)) => (path, Some(sub_field.to_string())),
+            None => (p.text(token), None),
+        };
+        let mut segments = path.split('.').map(str::to_string);
+        let name = segments.next().unwrap_or_default();
+        let properties = segments.collect();
+        if sub_field.is_none() {
+            p.skip_trivia();
         }
-    }
-    Ok(Variable {
-        name,
-        properties,
-        sub_field,
-        span,
+        Ok(Variable {
+            name,
+            properties,
+            sub_field,
+            span: Span(start, p.offset()),
+        })
     })
 }
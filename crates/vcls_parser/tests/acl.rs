@@ -1,5 +1,5 @@
 use vcls_ast::*;
-use vcls_parser::parse;
+use vcls_parser::{parse, ErrorCode};
 
 #[test]
 fn blank() {
@@ -7,7 +7,7 @@ fn blank() {
         parse("acl foo {}").unwrap(),
         Vcl {
             declarations: vec![Declaration::Acl(AclDeclaration {
-                name: "foo".to_string(),
+                name: Ident::new("foo", Span(4, 7)),
                 entries: vec![],
                 span: Span(0, 10),
             })],
@@ -21,11 +21,13 @@ fn localhost() {
         parse(r#"acl foo { "localhost"; }"#).unwrap(),
         Vcl {
             declarations: vec![Declaration::Acl(AclDeclaration {
-                name: "foo".to_string(),
+                name: Ident::new("foo", Span(4, 7)),
                 entries: vec![AclEntry {
                     negated: false,
-                    addr: "localhost".to_string(),
-                    cidr: 0,
+                    addr: AclAddress::Host {
+                        name: "localhost".to_string(),
+                        prefix: None,
+                    },
                     span: Span(10, 21),
                 }],
                 span: Span(0, 24),
@@ -40,11 +42,10 @@ fn ipv4() {
         parse(r#"acl foo { "192.0.2.0"; }"#).unwrap(),
         Vcl {
             declarations: vec![Declaration::Acl(AclDeclaration {
-                name: "foo".to_string(),
+                name: Ident::new("foo", Span(4, 7)),
                 entries: vec![AclEntry {
                     negated: false,
-                    addr: "192.0.2.0".to_string(),
-                    cidr: 0,
+                    addr: AclAddress::Network(IpNetwork::host("192.0.2.0".parse().unwrap())),
                     span: Span(10, 21),
                 }],
                 span: Span(0, 24),
@@ -59,11 +60,12 @@ fn ipv6() {
         parse(r#"acl foo { "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff"; }"#).unwrap(),
         Vcl {
             declarations: vec![Declaration::Acl(AclDeclaration {
-                name: "foo".to_string(),
+                name: Ident::new("foo", Span(4, 7)),
                 entries: vec![AclEntry {
                     negated: false,
-                    addr: "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff".to_string(),
-                    cidr: 0,
+                    addr: AclAddress::Network(IpNetwork::host(
+                        "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff".parse().unwrap()
+                    )),
                     span: Span(10, 50),
                 }],
                 span: Span(0, 53),
@@ -77,29 +79,35 @@ fn cidr() {
     assert_eq!(
         parse(
             r#"acl foo {
-                "1920.2.0"/24;
+                "192.0.2.0"/24;
                 "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff"/64;
             }"#
         )
         .unwrap(),
         Vcl {
             declarations: vec![Declaration::Acl(AclDeclaration {
-                name: "foo".to_string(),
+                name: Ident::new("foo", Span(4, 7)),
                 entries: vec![
                     AclEntry {
                         negated: false,
-                        addr: "1920.2.0".to_string(),
-                        cidr: 24,
-                        span: Span(26, 39),
+                        addr: AclAddress::Network(
+                            IpNetwork::new("192.0.2.0".parse().unwrap(), 24).unwrap()
+                        ),
+                        span: Span(26, 40),
                     },
                     AclEntry {
                         negated: false,
-                        addr: "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff".to_string(),
-                        cidr: 64,
-                        span: Span(57, 100),
+                        addr: AclAddress::Network(
+                            IpNetwork::new(
+                                "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff".parse().unwrap(),
+                                64
+                            )
+                            .unwrap()
+                        ),
+                        span: Span(58, 101),
                     }
                 ],
-                span: Span(0, 115),
+                span: Span(0, 116),
             })],
         },
     );
@@ -110,29 +118,35 @@ fn negation() {
     assert_eq!(
         parse(
             r#"acl foo {
-                ! "1920.2.0"/24;
+                ! "192.0.2.0"/24;
                 ! "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff"/64;
             }"#
         )
         .unwrap(),
         Vcl {
             declarations: vec![Declaration::Acl(AclDeclaration {
-                name: "foo".to_string(),
+                name: Ident::new("foo", Span(4, 7)),
                 entries: vec![
                     AclEntry {
                         negated: true,
-                        addr: "1920.2.0".to_string(),
-                        cidr: 24,
-                        span: Span(26, 41),
+                        addr: AclAddress::Network(
+                            IpNetwork::new("192.0.2.0".parse().unwrap(), 24).unwrap()
+                        ),
+                        span: Span(26, 42),
                     },
                     AclEntry {
                         negated: true,
-                        addr: "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff".to_string(),
-                        cidr: 64,
-                        span: Span(59, 104),
+                        addr: AclAddress::Network(
+                            IpNetwork::new(
+                                "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff".parse().unwrap(),
+                                64
+                            )
+                            .unwrap()
+                        ),
+                        span: Span(60, 105),
                     }
                 ],
-                span: Span(0, 119),
+                span: Span(0, 120),
             })],
         },
     );
@@ -154,36 +168,38 @@ acl office_ip_ranges {
         .unwrap(),
         Vcl {
             declarations: vec![Declaration::Acl(AclDeclaration {
-                name: "office_ip_ranges".to_string(),
+                name: Ident::new("office_ip_ranges", Span(5, 21)),
                 entries: vec![
                     AclEntry {
                         negated: false,
-                        addr: "localhost".to_string(),
-                        cidr: 0,
+                        addr: AclAddress::Host {
+                            name: "localhost".to_string(),
+                            prefix: None,
+                        },
                         span: Span(28, 39),
                     },
                     AclEntry {
                         negated: false,
-                        addr: "192.0.2.0".to_string(),
-                        cidr: 24,
+                        addr: AclAddress::Network(
+                            IpNetwork::new("192.0.2.0".parse().unwrap(), 24).unwrap()
+                        ),
                         span: Span(88, 102),
                     },
                     AclEntry {
                         negated: true,
-                        addr: "192.0.2.12".to_string(),
-                        cidr: 0,
+                        addr: AclAddress::Network(IpNetwork::host("192.0.2.12".parse().unwrap())),
                         span: Span(158, 172),
                     },
                     AclEntry {
                         negated: false,
-                        addr: "198.51.100.4".to_string(),
-                        cidr: 0,
+                        addr: AclAddress::Network(IpNetwork::host("198.51.100.4".parse().unwrap())),
                         span: Span(244, 258),
                     },
                     AclEntry {
                         negated: false,
-                        addr: "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff".to_string(),
-                        cidr: 0,
+                        addr: AclAddress::Network(IpNetwork::host(
+                            "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff".parse().unwrap()
+                        )),
                         span: Span(313, 353),
                     }
                 ],
@@ -192,3 +208,73 @@ acl office_ip_ranges {
         }
     );
 }
+
+#[test]
+fn invalid_entries() {
+    let src = r#"acl foo {
+    "1920.2.0";
+    "192.0.2.0"/33;
+    "2001:db8::"/999;
+    "2001:db8::"/129;
+    "2001:db8::"/64;
+    "localhost"/24;
+}"#;
+    let errors = parse(src).unwrap_err();
+    let found: Vec<(ErrorCode, &str)> = errors
+        .iter()
+        .map(|e| (e.code, &src[e.span.0..e.span.1]))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (ErrorCode::InvalidAclEntry, r#""1920.2.0""#),
+            (ErrorCode::InvalidAclEntry, "33"),
+            (ErrorCode::InvalidAclEntry, "999"),
+            (ErrorCode::InvalidAclEntry, "129"),
+        ]
+    );
+    assert_eq!(errors[1].notes, vec!["IPv4 prefixes are 0 to 32"]);
+}
+
+fn overlaps(entries: &str) -> Vec<(AclOverlapKind, usize, Option<usize>)> {
+    let vcl = parse(&format!("acl foo {{ {} }}", entries)).unwrap();
+    let Declaration::Acl(acl) = &vcl.declarations[0] else {
+        panic!("Unexpected declaration: {:?}", vcl.declarations[0]);
+    };
+    acl.overlaps()
+        .into_iter()
+        .map(|o| (o.kind, o.entry, o.other))
+        .collect()
+}
+
+#[test]
+fn overlap() {
+    assert_eq!(
+        overlaps(r#""192.0.2.0"/24; "192.0.2.12"/24; ! "192.0.2.0"/24; "localhost";"#),
+        vec![
+            (AclOverlapKind::Duplicate, 1, Some(0)),
+            (AclOverlapKind::Conflicting, 2, Some(0)),
+        ]
+    );
+    assert_eq!(
+        overlaps(
+            r#""10.0.0.0"/8; ! "10.1.0.0"/16; "10.1.2.0"/24; "10.2.0.0"/16; ! "10.1.3.0"/24; ! "192.0.2.1"; "::1";"#
+        ),
+        vec![
+            (AclOverlapKind::Shadowed, 3, Some(0)),
+            (AclOverlapKind::Shadowed, 4, Some(1)),
+            (AclOverlapKind::UselessNegation, 5, None),
+        ]
+    );
+}
+
+#[test]
+fn overlap_large_acl() {
+    let entries: String = (0..50_000u32)
+        .map(|i| format!("\"10.{}.{}.{}\"; ", i >> 16, (i >> 8) & 255, i & 255))
+        .collect();
+    let found = overlaps(&format!("\"10.0.0.0\"/8; {} \"10.0.0.1\";", entries));
+    assert_eq!(found.len(), 50_001);
+    assert_eq!(found[0], (AclOverlapKind::Shadowed, 1, Some(0)));
+    assert_eq!(found[50_000], (AclOverlapKind::Duplicate, 50_001, Some(2)));
+}
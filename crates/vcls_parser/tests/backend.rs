@@ -1,5 +1,5 @@
 use vcls_ast::*;
-use vcls_parser::parse;
+use vcls_parser::{parse, parse_recovering, ErrorCode, Severity};
 
 #[test]
 fn blank() {
@@ -7,11 +7,12 @@ fn blank() {
         parse("backend foo {}").unwrap(),
         Vcl {
             declarations: vec![Declaration::Backend(BackendDeclaration {
-                name: "foo".to_string(),
+                name: Ident::new("foo", Span(8, 11)),
                 config: Object {
                     entries: vec![],
                     span: Span(12, 14)
                 },
+                settings: Box::default(),
                 span: Span(0, 14),
             })],
         },
@@ -24,13 +25,15 @@ fn host() {
         parse(r#"backend foo { .host = "example.com"; }"#).unwrap(),
         Vcl {
             declarations: vec![Declaration::Backend(BackendDeclaration {
-                name: "foo".to_string(),
+                name: Ident::new("foo", Span(8, 11)),
                 config: Object {
                     entries: vec![(
-                        "host".to_string(),
+                        Ident::new("host", Span(15, 19)),
                         ObjectValue::Literal(Literal::String(StringLiteral {
                             tokens: vec![StringToken {
+                                kind: StringKind::Quoted,
                                 value: "example.com".to_string(),
+                                raw: r#""example.com""#.to_string(),
                                 span: Span(22, 35),
                             }],
                             span: Span(22, 35),
@@ -38,6 +41,10 @@ fn host() {
                     )],
                     span: Span(12, 38),
                 },
+                settings: Box::new(BackendConfig {
+                    host: Some("example.com".to_string()),
+                    ..Default::default()
+                }),
                 span: Span(0, 38),
             })],
         },
@@ -80,77 +87,87 @@ backend backend_name {
         .unwrap(),
         Vcl {
             declarations: vec![Declaration::Backend(BackendDeclaration {
-                name: "backend_name".to_string(),
+                name: Ident::new("backend_name", Span(9, 21)),
                 config: Object {
                     entries: vec![
                         (
-                            "dynamic".to_string(),
+                            Ident::new("dynamic", Span(83, 90)),
                             ObjectValue::Literal(Literal::Bool(BoolLiteral {
                                 value: true,
                                 span: Span(93, 97)
                             })),
                         ),
                         (
-                            "host".to_string(),
+                            Ident::new("host", Span(127, 131)),
                             ObjectValue::Literal(Literal::String(StringLiteral {
                                 tokens: vec![StringToken {
+                                    kind: StringKind::Quoted,
                                     value: "storage.googleapis.com".to_string(),
+                                    raw: r#""storage.googleapis.com""#.to_string(),
                                     span: Span(134, 158),
                                 },],
                                 span: Span(134, 158),
                             })),
                         ),
                         (
-                            "ssl".to_string(),
+                            Ident::new("ssl", Span(165, 168)),
                             ObjectValue::Literal(Literal::Bool(BoolLiteral {
                                 value: true,
                                 span: Span(171, 175)
                             }))
                         ),
                         (
-                            "ssl_check_cert".to_string(),
-                            ObjectValue::Ident("always".to_string()),
+                            Ident::new("ssl_check_cert", Span(182, 196)),
+                            ObjectValue::Ident(Ident::new("always", Span(199, 205))),
                         ),
                         (
-                            "connect_timeout".to_string(),
+                            Ident::new("connect_timeout", Span(239, 254)),
                             ObjectValue::Literal(Literal::RTime(RTimeLiteral {
                                 value: RelativeTime::from_sec(1.0),
                                 span: Span(257, 259)
                             })),
                         ),
                         (
-                            "max_connections".to_string(),
+                            Ident::new("max_connections", Span(266, 281)),
                             ObjectValue::Literal(Literal::Integer(IntegerLiteral {
                                 value: 200,
+                                raw: "200".to_string(),
                                 span: Span(284, 287)
                             })),
                         ),
                         (
-                            "probe".to_string(),
+                            Ident::new("probe", Span(314, 319)),
                             ObjectValue::Literal(Literal::Object(Object {
                                 entries: vec![
                                     (
-                                        "dummy".to_string(),
+                                        Ident::new("dummy", Span(333, 338)),
                                         ObjectValue::Literal(Literal::Bool(BoolLiteral {
                                             value: false,
                                             span: Span(341, 346)
                                         })),
                                     ),
                                     (
-                                        "request".to_string(),
+                                        Ident::new("request", Span(550, 557)),
                                         ObjectValue::Literal(Literal::String(StringLiteral {
                                             tokens: vec![
                                                 StringToken {
+                                                    kind: StringKind::Quoted,
                                                     value: "HEAD / HTTP/1.1".to_string(),
+                                                    raw: r#""HEAD / HTTP/1.1""#.to_string(),
                                                     span: Span(560, 577),
                                                 },
                                                 StringToken {
+                                                    kind: StringKind::Quoted,
                                                     value: "Host: storage.googleapis.com"
                                                         .to_string(),
+                                                    raw: r#""Host: storage.googleapis.com""#
+                                                        .to_string(),
                                                     span: Span(579, 609),
                                                 },
                                                 StringToken {
+                                                    kind: StringKind::Quoted,
                                                     value: "Connection: close".to_string(),
+                                                    raw: r#""Connection: close""#.to_string(),
                                                     span: Span(610, 629),
                                                 },
                                             ],
@@ -158,44 +175,48 @@ backend backend_name {
                                         })),
                                     ),
                                     (
-                                        "expected_response".to_string(),
+                                        Ident::new("expected_response", Span(640, 657)),
                                         ObjectValue::Literal(Literal::Integer(IntegerLiteral {
                                             value: 200,
+                                            raw: "200".to_string(),
                                             span: Span(660, 663)
                                         })),
                                     ),
                                     (
-                                        "interval".to_string(),
+                                        Ident::new("interval", Span(674, 682)),
                                         ObjectValue::Literal(Literal::RTime(RTimeLiteral {
                                             value: RelativeTime::from_sec(60.0),
                                             span: Span(685, 688)
                                         })),
                                     ),
                                     (
-                                        "timeout".to_string(),
+                                        Ident::new("timeout", Span(726, 733)),
                                         ObjectValue::Literal(Literal::RTime(RTimeLiteral {
                                             value: RelativeTime::from_sec(2.0),
                                             span: Span(736, 738)
                                         })),
                                     ),
                                     (
-                                        "window".to_string(),
+                                        Ident::new("window", Span(810, 816)),
                                         ObjectValue::Literal(Literal::Integer(IntegerLiteral {
                                             value: 5,
+                                            raw: "5".to_string(),
                                             span: Span(819, 820)
                                         })),
                                     ),
                                     (
-                                        "initial".to_string(),
+                                        Ident::new("initial", Span(866, 873)),
                                         ObjectValue::Literal(Literal::Integer(IntegerLiteral {
                                             value: 4,
+                                            raw: "4".to_string(),
                                             span: Span(876, 877)
                                         })),
                                     ),
                                     (
-                                        "threshold".to_string(),
+                                        Ident::new("threshold", Span(941, 950)),
                                         ObjectValue::Literal(Literal::Integer(IntegerLiteral {
                                             value: 4,
+                                            raw: "4".to_string(),
                                             span: Span(953, 954)
                                         })),
                                     ),
@@ -206,8 +227,158 @@ backend backend_name {
                     ],
                     span: Span(22, 1036),
                 },
+                settings: Box::new(BackendConfig {
+                    host: Some("storage.googleapis.com".to_string()),
+                    dynamic: Some(true),
+                    ssl: Some(true),
+                    ssl_check_cert: Some(true),
+                    connect_timeout: Some(RelativeTime::from_sec(1.0)),
+                    max_connections: Some(200),
+                    probe: Some(ProbeConfig {
+                        dummy: Some(false),
+                        request: Some(vec![
+                            "HEAD / HTTP/1.1".to_string(),
+                            "Host: storage.googleapis.com".to_string(),
+                            "Connection: close".to_string(),
+                        ]),
+                        expected_response: Some(200),
+                        interval: Some(RelativeTime::from_sec(60.0)),
+                        timeout: Some(RelativeTime::from_sec(2.0)),
+                        window: Some(5),
+                        initial: Some(4),
+                        threshold: Some(4),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
                 span: Span(1, 1036),
             })],
         },
     );
 }
+
+#[test]
+fn invalid_properties() {
+    let src = r#"backend foo {
+  .conect_timeout = 1s;
+  .port = "abc";
+  .ssl = "yes";
+  .ssl_check_cert = sometimes;
+}"#;
+    let (_, errors) = parse_recovering(src);
+    let errors: Vec<_> = errors
+        .iter()
+        .map(|e| (e.code, &src[e.span.0..e.span.1], e.message.as_str()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (
+                ErrorCode::UnknownProperty,
+                ".conect_timeout = 1s;",
+                "Property .conect_timeout is not allowed here"
+            ),
+            (
+                ErrorCode::InvalidPropertyValue,
+                r#".port = "abc";"#,
+                ".port must be a port number from 1 to 65535"
+            ),
+            (
+                ErrorCode::InvalidPropertyValue,
+                r#".ssl = "yes";"#,
+                ".ssl must be true or false"
+            ),
+            (
+                ErrorCode::InvalidPropertyValue,
+                ".ssl_check_cert = sometimes;",
+                ".ssl_check_cert must be `always` or `never`"
+            ),
+        ]
+    );
+    let (_, errors) = parse_recovering(src);
+    assert_eq!(
+        errors[0].notes,
+        vec!["did you mean .connect_timeout?".to_string()]
+    );
+}
+
+#[test]
+fn incoherent_properties() {
+    let src = r#"backend foo {
+  .ssl = true;
+  .probe = { .window = 3; .threshold = 4; }
+}
+backend bar {
+  .ssl_sni_hostname = "example.com";
+}
+backend baz {
+  .ssl = true;
+  .ssl_check_cert = never;
+}"#;
+    let (_, errors) = parse_recovering(src);
+    let errors: Vec<_> = errors
+        .iter()
+        .map(|e| (e.code, &src[e.span.0..e.span.1]))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (ErrorCode::InvalidPropertyValue, ".threshold = 4;"),
+            (ErrorCode::IneffectiveProperty, ".ssl = true;"),
+            (
+                ErrorCode::IneffectiveProperty,
+                r#".ssl_sni_hostname = "example.com";"#
+            ),
+        ]
+    );
+}
+
+#[test]
+fn ineffective_properties_are_warnings() {
+    let src = r#"backend foo {
+  .ssl = true;
+  .ssl_check_cert = always;
+}
+backend bar {
+  .ssl_sni_hostname = "example.com";
+}"#;
+    let (_, errors) = parse_recovering(src);
+    assert_eq!(errors.len(), 2);
+    assert!(errors.iter().all(|e| e.severity == Severity::Warning));
+    assert!(parse(src).is_ok());
+}
+
+#[test]
+fn connection_properties() {
+    let src = r#"backend foo {
+  .host = "example.com";
+  .ssl_client_cert = "cert";
+  .ssl_client_key = "key";
+  .prefer_ipv6 = true;
+  .bypass_local_route_table = false;
+  .keepalive_time = 30s;
+  .tcp_keepalive_enable = true;
+  .tcp_keepalive_time = 60s;
+  .tcp_keepalive_interval = 10s;
+  .tcp_keepalive_probes = 5;
+}"#;
+    let Declaration::Backend(backend) = parse(src).unwrap().declarations.remove(0) else {
+        panic!("Expected a backend");
+    };
+    assert_eq!(
+        *backend.settings,
+        BackendConfig {
+            host: Some("example.com".to_string()),
+            ssl_client_cert: Some("cert".to_string()),
+            ssl_client_key: Some("key".to_string()),
+            prefer_ipv6: Some(true),
+            bypass_local_route_table: Some(false),
+            keepalive_time: Some(RelativeTime::from_sec(30.0)),
+            tcp_keepalive_enable: Some(true),
+            tcp_keepalive_time: Some(RelativeTime::from_sec(60.0)),
+            tcp_keepalive_interval: Some(RelativeTime::from_sec(10.0)),
+            tcp_keepalive_probes: Some(5),
+            ..Default::default()
+        }
+    );
+}
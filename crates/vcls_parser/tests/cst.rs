@@ -0,0 +1,91 @@
+use vcls_ast::*;
+use vcls_parser::{cst::SyntaxKind, lexer::TokenKind, parse, parse_lossless};
+
+const SRC: &str = r#"# Change history
+# 2023-01-01: added foo
+acl foo {
+  "localhost"; // loopback
+  !"192.168.0.0"/16;
+}
+
+/* main entry */
+sub vcl_recv {
+  # strip cookies
+  unset req.http.Cookie;
+  if (req.http.X-Foo == "bar") {
+    set req.http.X-Bar = std.tolower(req.http.X-Foo) "baz";
+  } else {
+    return(pass);
+  }
+}
+"#;
+
+#[test]
+fn roundtrip() {
+    let tree = parse_lossless(SRC).unwrap();
+    assert_eq!(tree.to_string(), SRC);
+    assert_eq!(tree.vcl(), &parse(SRC).unwrap());
+}
+
+#[test]
+fn structure() {
+    let tree = parse_lossless(SRC).unwrap();
+    let kinds: Vec<SyntaxKind> = tree.root().child_nodes().map(|n| n.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            SyntaxKind::AclDeclaration,
+            SyntaxKind::SubroutineDeclaration
+        ]
+    );
+    let sub = tree.root().child_nodes().nth(1).unwrap();
+    assert!(tree.text(sub.span).starts_with("sub vcl_recv {"));
+    assert!(tree.text(sub.span).ends_with('}'));
+    assert!(sub
+        .descendants()
+        .iter()
+        .any(|n| n.kind == SyntaxKind::CallExpression
+            && tree.text(n.span) == "std.tolower(req.http.X-Foo)"));
+}
+
+#[test]
+fn ast_view() {
+    let tree = parse_lossless(SRC).unwrap();
+    let Declaration::Subroutine(sub) = &tree.vcl().declarations[1] else {
+        panic!("expected a subroutine");
+    };
+    let Statement::Unset(unset) = &sub.body[0] else {
+        panic!("expected an unset statement");
+    };
+    let node = tree.covering_node(unset.span);
+    assert_eq!(node.kind, SyntaxKind::UnsetStatement);
+    assert_eq!(tree.text(node.span), "unset req.http.Cookie;");
+    let comments: Vec<&str> = tree
+        .leading_comments(node.span)
+        .iter()
+        .map(|t| t.text(SRC))
+        .collect();
+    assert_eq!(comments, vec!["# strip cookies"]);
+}
+
+#[test]
+fn comments() {
+    let tree = parse_lossless(SRC).unwrap();
+    let acl = tree.root().child_nodes().next().unwrap();
+    let comments: Vec<&str> = tree
+        .leading_comments(acl.span)
+        .iter()
+        .map(|t| t.text(SRC))
+        .collect();
+    assert_eq!(
+        comments,
+        vec!["# Change history", "# 2023-01-01: added foo"]
+    );
+    let trailing: Vec<&str> = acl
+        .tokens()
+        .into_iter()
+        .filter(|t| t.kind == TokenKind::SlashComment)
+        .map(|t| t.text(SRC))
+        .collect();
+    assert_eq!(trailing, vec!["// loopback"]);
+}
@@ -0,0 +1,170 @@
+use vcls_ast::*;
+use vcls_parser::{
+    parse, parse_declaration, parse_declaration_with_dialect, parse_lossless,
+    parse_lossless_with_dialect, parse_recovering, parse_statements, parse_statements_with_dialect,
+    parse_with_dialect, Dialect, ErrorCode,
+};
+
+const SRC: &str = r#"vcl 4.1;
+import directors;
+probe healthcheck {
+  .url = "/health";
+  .interval = 5s;
+}
+sub vcl_init {
+  new cluster = directors.round_robin();
+}
+sub vcl_recv {
+  if (req.method == "PURGE") {
+    return(purge);
+  }
+  return (synth(404, "Not Found"));
+}
+"#;
+
+#[test]
+fn varnish() {
+    let vcl = parse_with_dialect(SRC, Dialect::Varnish).unwrap();
+    assert_eq!(
+        vcl.declarations[0],
+        Declaration::Version(VersionDeclaration {
+            version: "4.1".to_string(),
+            span: Span(0, 8),
+        })
+    );
+    let Declaration::Probe(probe) = &vcl.declarations[2] else {
+        panic!("Unexpected declaration: {:?}", vcl.declarations[2]);
+    };
+    assert_eq!(probe.name, "healthcheck");
+    assert_eq!(probe.config.entries[0].0, "url");
+    let Declaration::Subroutine(init) = &vcl.declarations[3] else {
+        panic!("Unexpected declaration: {:?}", vcl.declarations[3]);
+    };
+    let Statement::New(new) = &init.body[0] else {
+        panic!("Unexpected statement: {:?}", init.body[0]);
+    };
+    assert_eq!(new.name, "cluster");
+    assert_eq!(new.constructor.target.name, "directors");
+    assert_eq!(new.constructor.target.properties, vec!["round_robin"]);
+    let Declaration::Subroutine(recv) = &vcl.declarations[4] else {
+        panic!("Unexpected declaration: {:?}", vcl.declarations[4]);
+    };
+    let Statement::Return(ret) = &recv.body[1] else {
+        panic!("Unexpected statement: {:?}", recv.body[1]);
+    };
+    let Some(ReturnValue::Action(ReturnAction::Synth(arguments))) = &ret.value else {
+        panic!("Unexpected return: {:?}", ret);
+    };
+    assert_eq!(arguments.len(), 2);
+}
+
+#[test]
+fn fastly_rejects_varnish_constructs() {
+    let (_, errors) = parse_recovering(SRC);
+    let found: Vec<(ErrorCode, &str)> = errors
+        .iter()
+        .map(|e| (e.code, &SRC[e.span.0..e.span.1]))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (ErrorCode::UnsupportedInDialect, "vcl 4.1;"),
+            (ErrorCode::UnsupportedInDialect, "probe"),
+            (ErrorCode::UnsupportedInDialect, "new"),
+            (ErrorCode::UnsupportedInDialect, "purge"),
+            (
+                ErrorCode::UnsupportedInDialect,
+                r#"synth(404, "Not Found")"#
+            ),
+        ]
+    );
+    assert_eq!(
+        errors[0].message,
+        "`vcl` version declaration is only supported in the Varnish dialect"
+    );
+    assert!(parse(SRC).is_err());
+}
+
+#[test]
+fn varnish_accepts_fastly() {
+    let src = "sub vcl_fetch { #FASTLY fetch\n return(deliver_stale); }";
+    assert_eq!(parse_with_dialect(src, Dialect::Varnish), parse(src));
+}
+
+#[test]
+fn varnish_backend() {
+    let src = r#"vcl 4.1;
+probe healthcheck { .url = "/health"; }
+backend proxy { .host = "10.0.0.1"; }
+backend origin {
+  .host = "example.com";
+  .probe = healthcheck;
+  .via = proxy;
+  .proxy_header = 2;
+  .backend_wait_timeout = 2s;
+}
+backend socket { .path = "/run/origin.sock"; }
+"#;
+    let vcl = parse_with_dialect(src, Dialect::Varnish).unwrap();
+    let Declaration::Backend(origin) = &vcl.declarations[3] else {
+        panic!("Unexpected declaration: {:?}", vcl.declarations[3]);
+    };
+    let settings = &origin.settings;
+    assert_eq!(settings.probe_name.as_ref().unwrap(), "healthcheck");
+    assert_eq!(settings.via.as_ref().unwrap(), "proxy");
+    assert_eq!(settings.proxy_header, Some(2));
+    assert_eq!(
+        settings.backend_wait_timeout,
+        Some(RelativeTime::from_sec(2.0))
+    );
+    let Declaration::Backend(socket) = &vcl.declarations[4] else {
+        panic!("Unexpected declaration: {:?}", vcl.declarations[4]);
+    };
+    assert_eq!(socket.settings.path.as_deref(), Some("/run/origin.sock"));
+
+    let src = "backend b { .host = \"h\"; .probe = missing; .proxy_header = 3; }\n";
+    let errors = parse_with_dialect(src, Dialect::Varnish).unwrap_err();
+    let found: Vec<(ErrorCode, &str)> = errors
+        .iter()
+        .map(|e| (e.code, &src[e.span.0..e.span.1]))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (ErrorCode::InvalidPropertyValue, ".proxy_header = 3;"),
+            (ErrorCode::UndefinedReference, "missing"),
+        ]
+    );
+
+    // Fastly has no named probes or Varnish properties.
+    let src = "backend b { .host = \"h\"; .probe = p; .via = b; }\n";
+    let codes: Vec<ErrorCode> = parse(src).unwrap_err().iter().map(|e| e.code).collect();
+    assert_eq!(
+        codes,
+        vec![ErrorCode::InvalidPropertyValue, ErrorCode::UnknownProperty]
+    );
+}
+
+#[test]
+fn entry_points() {
+    let tree = parse_lossless_with_dialect(SRC, Dialect::Varnish).unwrap();
+    assert_eq!(tree.source(), SRC);
+    assert_eq!(tree.vcl().declarations.len(), 5);
+    assert!(parse_lossless(SRC).is_err());
+
+    let probe = "probe p { .url = \"/\"; }";
+    assert!(matches!(
+        parse_declaration_with_dialect(probe, Dialect::Varnish),
+        Ok(Declaration::Probe(_))
+    ));
+    assert!(parse_declaration(probe).is_err());
+
+    let statements = "new cluster = directors.round_robin();\nreturn (purge);";
+    assert_eq!(
+        parse_statements_with_dialect(statements, Dialect::Varnish)
+            .unwrap()
+            .len(),
+        2
+    );
+    assert!(parse_statements(statements).is_err());
+}
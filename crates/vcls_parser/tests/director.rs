@@ -0,0 +1,161 @@
+use vcls_ast::*;
+use vcls_parser::{parse, ErrorCode};
+
+#[test]
+fn random() {
+    assert_eq!(
+        parse(
+            r#"director foo random {
+  .quorum = 50%;
+  .retries = 3;
+  { .backend = F_a; .weight = 2; }
+}"#
+        )
+        .unwrap(),
+        Vcl {
+            declarations: vec![Declaration::Director(DirectorDeclaration {
+                name: Ident::new("foo", Span(9, 12)),
+                typ: DirectorType::Random,
+                config: Some(Object {
+                    entries: vec![
+                        (
+                            Ident::new("quorum", Span(25, 31)),
+                            ObjectValue::Literal(Literal::Percentage(PercentageLiteral {
+                                value: 50.0,
+                                span: Span(34, 37),
+                            })),
+                        ),
+                        (
+                            Ident::new("retries", Span(42, 49)),
+                            ObjectValue::Literal(Literal::Integer(IntegerLiteral {
+                                value: 3,
+                                raw: "3".to_string(),
+                                span: Span(52, 53),
+                            })),
+                        ),
+                    ],
+                    span: Span(20, 91),
+                }),
+                directions: vec![Object {
+                    entries: vec![
+                        (
+                            Ident::new("backend", Span(60, 67)),
+                            ObjectValue::Ident(Ident::new("F_a", Span(70, 73)))
+                        ),
+                        (
+                            Ident::new("weight", Span(76, 82)),
+                            ObjectValue::Literal(Literal::Integer(IntegerLiteral {
+                                value: 2,
+                                raw: "2".to_string(),
+                                span: Span(85, 86),
+                            })),
+                        ),
+                    ],
+                    span: Span(57, 89),
+                }],
+                span: Span(0, 91),
+            })],
+        },
+    );
+}
+
+#[test]
+fn fallback() {
+    assert_eq!(
+        parse("director foo fallback { { .backend = F_a; } { .backend = F_b; } }").unwrap(),
+        Vcl {
+            declarations: vec![Declaration::Director(DirectorDeclaration {
+                name: Ident::new("foo", Span(9, 12)),
+                typ: DirectorType::Fallback,
+                config: None,
+                directions: vec![
+                    Object {
+                        entries: vec![(
+                            Ident::new("backend", Span(27, 34)),
+                            ObjectValue::Ident(Ident::new("F_a", Span(37, 40))),
+                        )],
+                        span: Span(24, 43),
+                    },
+                    Object {
+                        entries: vec![(
+                            Ident::new("backend", Span(47, 54)),
+                            ObjectValue::Ident(Ident::new("F_b", Span(57, 60))),
+                        )],
+                        span: Span(44, 63),
+                    },
+                ],
+                span: Span(0, 65),
+            })],
+        },
+    );
+}
+
+#[test]
+fn types() {
+    for (keyword, typ) in [
+        ("hash", DirectorType::Content),
+        ("client", DirectorType::Client),
+    ] {
+        let src = format!(
+            "director foo {} {{ .quorum = 10%; {{ .backend = F_a; .weight = 1; }} }}",
+            keyword
+        );
+        match &parse(&src).unwrap().declarations[0] {
+            Declaration::Director(director) => assert_eq!(director.typ, typ),
+            d => panic!("Unexpected declaration: {:?}", d),
+        }
+    }
+}
+
+#[test]
+fn chash() {
+    let vcl = parse(
+        r#"director foo chash {
+  .key = object;
+  .seed = 12345;
+  .vnodes_per_node = 256;
+  .quorum = 50%;
+  { .backend = F_a; .id = "s1"; }
+  { .backend = F_b; .id = "s2"; }
+}"#,
+    )
+    .unwrap();
+    match &vcl.declarations[0] {
+        Declaration::Director(director) => {
+            assert_eq!(director.typ, DirectorType::ConsistentHashing);
+            assert_eq!(director.config.as_ref().unwrap().entries.len(), 4);
+            assert_eq!(director.directions.len(), 2);
+        }
+        d => panic!("Unexpected declaration: {:?}", d),
+    }
+}
+
+#[test]
+fn unknown_type() {
+    let errors = parse("director foo round_robin { { .backend = F_a; } }").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, ErrorCode::UnknownDirectorType);
+    assert_eq!(errors[0].span, Span(13, 24));
+}
+
+#[test]
+fn invalid_properties() {
+    let errors = parse(
+        r#"director foo fallback {
+  .quorum = 50%;
+  { .backend = "F_a"; .weight = 1; }
+  { }
+}"#,
+    )
+    .unwrap_err();
+    let errors: Vec<_> = errors.iter().map(|e| (e.code, e.span)).collect();
+    assert_eq!(
+        errors,
+        vec![
+            (ErrorCode::UnknownProperty, Span(26, 40)),
+            (ErrorCode::InvalidPropertyValue, Span(45, 62)),
+            (ErrorCode::UnknownProperty, Span(63, 75)),
+            (ErrorCode::MissingProperty, Span(80, 83)),
+        ]
+    );
+}
@@ -0,0 +1,41 @@
+use vcls_ast::*;
+use vcls_parser::{parse, ErrorCode, Severity};
+
+#[test]
+fn syntax_error() {
+    let errors = parse(r#"acl foo { "localhost" }"#).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    let error = &errors[0];
+    assert_eq!(error.code, ErrorCode::Syntax);
+    assert_eq!(error.code.as_str(), "E0001");
+    assert_eq!(error.severity, Severity::Error);
+    assert_eq!(error.span, Span(22, 22));
+    assert_eq!(error.message, "Expected ;, found `}`");
+    assert_eq!(error.expected, vec![";".to_string()]);
+}
+
+#[test]
+fn unknown_declaration() {
+    let errors = parse("sub foo {}\nfoo bar {}").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, ErrorCode::Syntax);
+    assert_eq!(errors[0].span, Span(11, 11));
+    assert!(errors[0].expected.contains(&"Declaration".to_string()));
+}
+
+#[test]
+fn display() {
+    let errors = parse("sub foo {").unwrap_err();
+    assert_eq!(
+        errors[0].to_string(),
+        "error[E0001]: Expected Statement or }, found end of input at 9..9 (expected Statement, })"
+    );
+}
+
+#[test]
+fn semantic_error() {
+    let errors = parse(r#"table t INTEGER { "a": 99999999999999999999 }"#).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, ErrorCode::InvalidNumber);
+    assert_eq!(errors[0].span, Span(23, 43));
+}
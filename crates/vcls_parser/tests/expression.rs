@@ -0,0 +1,145 @@
+use vcls_ast::*;
+use vcls_parser::parse;
+
+fn condition(expr: &str) -> Expression {
+    let src = format!("sub foo {{ if ({}) {{}} }}", expr);
+    match parse(&src).unwrap().declarations.remove(0) {
+        Declaration::Subroutine(mut sub) => match sub.body.remove(0) {
+            Statement::If(stmt) => stmt.condition,
+            s => panic!("Unexpected statement: {:?}", s),
+        },
+        d => panic!("Unexpected declaration: {:?}", d),
+    }
+}
+
+/// Renders an expression as an S-expression to compare tree shapes compactly.
+fn sexp(expr: &Expression) -> String {
+    match expr {
+        Expression::Binary(e) => {
+            let op = match e.operator {
+                BinaryOperator::Eq => "==",
+                BinaryOperator::Ne => "!=",
+                BinaryOperator::Lt => "<",
+                BinaryOperator::Le => "<=",
+                BinaryOperator::Gt => ">",
+                BinaryOperator::Ge => ">=",
+                BinaryOperator::AmpAmp => "&&",
+                BinaryOperator::BarBar => "||",
+                BinaryOperator::Add => "+",
+                BinaryOperator::Sub => "-",
+                BinaryOperator::Mul => "*",
+                BinaryOperator::Div => "/",
+                BinaryOperator::Tilde => "~",
+                BinaryOperator::NotTilde => "!~",
+            };
+            format!("({} {} {})", op, sexp(&e.lhs), sexp(&e.rhs))
+        }
+        Expression::Unary(e) => {
+            let op = match e.operator {
+                UnaryOperator::Not => "!",
+                UnaryOperator::Neg => "neg",
+            };
+            format!("({} {})", op, sexp(&e.rhs))
+        }
+        Expression::Concat(e) => {
+            let parts: Vec<String> = e.parts.iter().map(sexp).collect();
+            format!("(concat {})", parts.join(" "))
+        }
+        Expression::Paren(e) => format!("[{}]", sexp(&e.expr)),
+        Expression::Variable(v) => {
+            let mut name = v.name.clone();
+            for prop in &v.properties {
+                name.push('.');
+                name.push_str(prop);
+            }
+            name
+        }
+        Expression::Literal(Literal::String(s)) => {
+            let value: String = s.tokens.iter().map(|t| t.value.as_str()).collect();
+            format!("{:?}", value)
+        }
+        Expression::Literal(Literal::Integer(i)) => i.value.to_string(),
+        Expression::Literal(Literal::Bool(b)) => b.value.to_string(),
+        e => panic!("Unexpected expression: {:?}", e),
+    }
+}
+
+#[test]
+fn precedence() {
+    for (src, expected) in [
+        ("a && b", "(&& a b)"),
+        ("a || b", "(|| a b)"),
+        ("a || b && c", "(|| a (&& b c))"),
+        ("a && b || c", "(|| (&& a b) c)"),
+        ("a || b || c", "(|| (|| a b) c)"),
+        ("a && (b || c)", "(&& a [(|| b c)])"),
+        (
+            r#"req.http.A == "x" && req.http.B ~ "y""#,
+            r#"(&& (== req.http.A "x") (~ req.http.B "y"))"#,
+        ),
+        (
+            r#"a != "x" || b !~ "y" && c <= 3"#,
+            r#"(|| (!= a "x") (&& (!~ b "y") (<= c 3)))"#,
+        ),
+        ("a < 1 || a >= 10", "(|| (< a 1) (>= a 10))"),
+        ("a > 1 == true", "(== (> a 1) true)"),
+        ("a + 1 * 2 - 3 / 4", "(- (+ a (* 1 2)) (/ 3 4))"),
+        ("a - 1 - 2", "(- (- a 1) 2)"),
+        ("a / 1 * 2", "(* (/ a 1) 2)"),
+        ("!a && b", "(&& (! a) b)"),
+        ("!(a || b)", "(! [(|| a b)])"),
+        ("!a == !b", "(== (! a) (! b))"),
+        ("-a * 2", "(* (neg a) 2)"),
+        (r#"a ~ "x" == b !~ "y""#, r#"(!~ (== (~ a "x") b) "y")"#),
+        (r#""a" b "c" + d"#, r#"(+ (concat "a" b "c") d)"#),
+        (r#"("a" b) + "c""#, r#"(+ [(concat "a" b)] "c")"#),
+    ] {
+        assert_eq!(sexp(&condition(src)), expected, "{}", src);
+    }
+}
+
+#[test]
+fn spans() {
+    // sub foo { if (a && !b || c
+    // 0             14          26
+    let expr = condition("a && !b || c");
+    assert_eq!(expr.span(), Span(14, 26));
+    match expr {
+        Expression::Binary(or) => {
+            assert_eq!(or.operator_span, Span(22, 24));
+            assert_eq!(or.lhs.span(), Span(14, 22));
+            match *or.lhs {
+                Expression::Binary(and) => {
+                    assert_eq!(and.operator_span, Span(16, 18));
+                    assert_eq!(and.rhs.span(), Span(19, 22));
+                    match *and.rhs {
+                        Expression::Unary(not) => assert_eq!(not.operator_span, Span(19, 20)),
+                        e => panic!("Unexpected expression: {:?}", e),
+                    }
+                }
+                e => panic!("Unexpected expression: {:?}", e),
+            }
+        }
+        e => panic!("Unexpected expression: {:?}", e),
+    }
+}
+
+#[test]
+fn concat_and_paren_spans() {
+    // sub foo { if (("a" b) + "c"
+    // 0             14       23  27
+    let expr = condition(r#"("a" b) + "c""#);
+    let Expression::Binary(add) = expr else {
+        panic!("Unexpected expression: {:?}", expr);
+    };
+    assert_eq!(add.operator_span, Span(22, 23));
+    let Expression::Paren(paren) = *add.lhs else {
+        panic!("Unexpected expression: {:?}", add.lhs);
+    };
+    assert_eq!(paren.span, Span(14, 21));
+    let Expression::Concat(concat) = *paren.expr else {
+        panic!("Unexpected expression: {:?}", paren.expr);
+    };
+    assert_eq!(concat.span, Span(15, 20));
+    assert_eq!(concat.parts.len(), 2);
+}
@@ -0,0 +1,102 @@
+use vcls_ast::*;
+use vcls_parser::{cst::SyntaxKind, parse, parse_lossless, parse_recovering};
+
+const SRC: &str = r#"#FASTLY recv
+sub vcl_recv {
+  #FASTLY recv
+  set req.http.X = "1";
+}
+sub vcl_fetch {
+  if (beresp.status == 200) {
+    #FASTLY fetch
+  }
+}
+sub vcl_deliver {
+  # FASTLY deliver
+  #FASTLY deliver now
+  #FASTLY recv
+}
+sub vcl_log {
+}
+sub custom {
+}
+"#;
+
+fn subroutine(vcl: &Vcl, name: &str) -> SubroutineDeclaration {
+    vcl.declarations
+        .iter()
+        .find_map(|d| match d {
+            Declaration::Subroutine(sub) if sub.name == name => Some(sub.clone()),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn statement() {
+    let vcl = parse(SRC).unwrap();
+    let recv = subroutine(&vcl, "vcl_recv");
+    let start = SRC.find("  #FASTLY recv").unwrap() + 2;
+    assert_eq!(
+        recv.body[0],
+        Statement::FastlyMacro(FastlyMacroStatement {
+            span: Span(start, start + "#FASTLY recv".len()),
+            name: Ident::new("recv", Span(start + 8, start + 12)),
+        })
+    );
+    assert!(matches!(recv.body[1], Statement::Set(_)));
+}
+
+#[test]
+fn comments_elsewhere() {
+    let vcl = parse(SRC).unwrap();
+    assert_eq!(vcl.declarations.len(), 5);
+    let deliver = subroutine(&vcl, "vcl_deliver");
+    assert_eq!(deliver.body.len(), 1);
+    let vcl = parse("table t { #FASTLY recv\n \"k\": \"v\" }").unwrap();
+    assert_eq!(vcl.declarations.len(), 1);
+}
+
+#[test]
+fn missing() {
+    let vcl = parse(SRC).unwrap();
+    let missing: Vec<&str> = vcl
+        .missing_fastly_macros()
+        .iter()
+        .map(|sub| sub.name.as_str())
+        .collect();
+    assert_eq!(missing, vec!["vcl_deliver", "vcl_log"]);
+}
+
+#[test]
+fn recovering() {
+    let src = "sub vcl_recv {\n  #FASTLY recv\n  set;\n}\n";
+    let (vcl, errors) = parse_recovering(src);
+    assert_eq!(errors.len(), 1);
+    let recv = subroutine(&vcl, "vcl_recv");
+    assert!(matches!(recv.body[0], Statement::FastlyMacro(_)));
+    assert!(matches!(recv.body[1], Statement::Invalid(_)));
+    assert!(vcl.missing_fastly_macros().is_empty());
+}
+
+#[test]
+fn syntax_tree() {
+    let tree = parse_lossless(SRC).unwrap();
+    assert_eq!(tree.to_string(), SRC);
+    let start = SRC.find("#FASTLY fetch").unwrap();
+    let node = tree.covering_node(Span(start, start + 1));
+    assert_eq!(node.kind, SyntaxKind::FastlyMacroStatement);
+    assert_eq!(&SRC[node.span.0..node.span.1], "#FASTLY fetch");
+}
+
+#[test]
+fn name_span() {
+    let src = "sub vcl_recv {\n  #FASTLY \t recv  \n}\n";
+    let vcl = parse(src).unwrap();
+    let recv = subroutine(&vcl, "vcl_recv");
+    let Statement::FastlyMacro(statement) = &recv.body[0] else {
+        panic!("Unexpected statement: {:?}", recv.body[0]);
+    };
+    assert_eq!(statement.name, "recv");
+    assert_eq!(&src[statement.name.span.0..statement.name.span.1], "recv");
+}
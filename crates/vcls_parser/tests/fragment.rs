@@ -0,0 +1,65 @@
+use vcls_ast::*;
+use vcls_parser::{parse_declaration, parse_expression, parse_statements, ErrorCode};
+
+#[test]
+fn expression() {
+    let expression = parse_expression(r#" req.http.host ~ "example" "#).unwrap();
+    let Expression::Binary(binary) = expression else {
+        panic!("expected a binary expression, got {:?}", expression);
+    };
+    assert_eq!(binary.operator, BinaryOperator::Tilde);
+    assert_eq!(binary.span, Span(1, 27));
+}
+
+#[test]
+fn expression_trailing_input() {
+    let errors = parse_expression("1 + 2;").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, ErrorCode::Syntax);
+    assert_eq!(errors[0].span, Span(5, 5));
+}
+
+#[test]
+fn statements() {
+    let src = r#"
+#FASTLY recv
+set req.http.X = "1";
+if (req.http.Y) {
+  return(pass);
+}
+"#;
+    let statements = parse_statements(src).unwrap();
+    assert_eq!(statements.len(), 3);
+    assert!(matches!(statements[0], Statement::FastlyMacro(_)));
+    let Statement::Set(set) = &statements[1] else {
+        panic!("expected a set statement, got {:?}", statements[1]);
+    };
+    assert_eq!(&src[set.span.0..set.span.1], r#"set req.http.X = "1";"#);
+    assert!(matches!(statements[2], Statement::If(_)));
+    assert_eq!(parse_statements("").unwrap(), vec![]);
+}
+
+#[test]
+fn statements_errors() {
+    let errors = parse_statements("set req.http.X = \"1\";\n}").unwrap_err();
+    assert_eq!(errors[0].code, ErrorCode::Syntax);
+    assert_eq!(errors[0].span, Span(22, 22));
+    let errors = parse_statements("goto missing;").unwrap_err();
+    assert_eq!(errors[0].code, ErrorCode::UnknownLabel);
+}
+
+#[test]
+fn declaration() {
+    let declaration = parse_declaration("\nbackend foo {}\n").unwrap();
+    let Declaration::Backend(backend) = declaration else {
+        panic!("expected a backend, got {:?}", declaration);
+    };
+    assert_eq!(backend.name, "foo");
+    assert_eq!(backend.span, Span(1, 15));
+    // Backends cannot be resolved out of their file.
+    assert!(parse_declaration(r#"table t BACKEND { "a": foo }"#).is_ok());
+    let errors = parse_declaration(r#"table t INTEGER { "a": "b" }"#).unwrap_err();
+    assert_eq!(errors[0].code, ErrorCode::InvalidTableValue);
+    let errors = parse_declaration("backend foo {} backend bar {}").unwrap_err();
+    assert_eq!(errors[0].code, ErrorCode::Syntax);
+}
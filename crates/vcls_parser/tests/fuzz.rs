@@ -0,0 +1,273 @@
+//! Property test: every entry point returns `Ok` or diagnostics, and never panics, for
+//! arbitrary bytes and for sources spliced together from fragments of the grammar.
+//!
+//! The number of cases can be raised with `VCLS_FUZZ_CASES`, e.g.
+//! `VCLS_FUZZ_CASES=100000 cargo test -p vcls_parser --release --test fuzz`.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use vcls_ast::Span;
+use vcls_parser::{
+    parse, parse_declaration, parse_expression, parse_incremental, parse_incremental_with_dialect,
+    parse_lossless, parse_recovering, parse_recovering_with_dialect, parse_statements,
+    parse_with_dialect, Dialect, ErrorCode, TextEdit,
+};
+
+const FRAGMENTS: [&str; 94] = [
+    "sub ",
+    "vcl_recv",
+    " ",
+    "\n",
+    "{",
+    "}",
+    "(",
+    ")",
+    "[",
+    "]",
+    ";",
+    ",",
+    ":",
+    ".",
+    "=",
+    "+=",
+    "-=",
+    "*=",
+    "/=",
+    "%=",
+    "|=",
+    "&=",
+    "^=",
+    "<<=",
+    ">>=",
+    "rol=",
+    "ror=",
+    "&&=",
+    "||=",
+    "==",
+    "!=",
+    "~",
+    "!~",
+    "!",
+    "-",
+    "+",
+    "*",
+    "/",
+    "&&",
+    "||",
+    "<",
+    ">",
+    "<=",
+    ">=",
+    "\"",
+    "\"a\"",
+    "{\"",
+    "\"}",
+    "{abc\"x\"abc}",
+    "{X\"X}",
+    "{X\"\"X}",
+    "#",
+    "# c\n",
+    "/*",
+    "*/",
+    "//",
+    "#FASTLY recv\n",
+    "set ",
+    "req.http.X",
+    "req.http.X:y",
+    "if ",
+    "else ",
+    "elsif ",
+    "else if ",
+    "return",
+    "(pass)",
+    "synth(404, \"x\")",
+    "goto ",
+    "l:",
+    "declare local var.x INTEGER;",
+    "acl a ",
+    "\"10.0.0.0\"/8",
+    "!\"::1\"",
+    "/33",
+    "backend b ",
+    ".host = \"h\";",
+    ".probe = { .window = 1; }",
+    "director d random ",
+    "table t BACKEND ",
+    "\"k\": b",
+    "include \"x\";",
+    "import std;",
+    "vcl 4.1;",
+    "1",
+    "-9223372036854775808",
+    "99999999999999999999",
+    "1.5e400",
+    "10000000000000000000s",
+    "170141183460469231731687303.715884ms",
+    "-170141183460469231731687303.715884ms",
+    "0.0000000000000000000000000001ms",
+    "9223372036854775807.9999999999999999999",
+    "99999999999999999999999999999999999999999.5y",
+    "1.00000000000000000000000000000000000000001s",
+];
+
+/// xorshift64*, so that failures can be reproduced from the printed seed.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+fn cases() -> u64 {
+    std::env::var("VCLS_FUZZ_CASES")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(2000)
+}
+
+fn arbitrary_bytes(rng: &mut Rng) -> String {
+    let len = rng.below(64);
+    let bytes: Vec<u8> = (0..len).map(|_| rng.next() as u8).collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn grammar_soup(rng: &mut Rng) -> String {
+    let len = rng.below(40);
+    (0..len)
+        .map(|_| FRAGMENTS[rng.below(FRAGMENTS.len())])
+        .collect()
+}
+
+/// Runs every entry point on `src`.
+fn check(src: &str, rng: &mut Rng) {
+    let _ = parse(src);
+    let _ = parse_with_dialect(src, Dialect::Varnish);
+    let _ = parse_lossless(src);
+    let _ = parse_expression(src);
+    let _ = parse_statements(src);
+    let _ = parse_declaration(src);
+    let varnish = parse_recovering_with_dialect(src, Dialect::Varnish);
+    let previous = parse_recovering(src);
+
+    // An edit between two char boundaries.
+    let boundaries: Vec<usize> = (0..=src.len())
+        .filter(|&i| src.is_char_boundary(i))
+        .collect();
+    let mut a = boundaries[rng.below(boundaries.len())];
+    let mut b = boundaries[rng.below(boundaries.len())];
+    if a > b {
+        std::mem::swap(&mut a, &mut b);
+    }
+    let text = FRAGMENTS[rng.below(FRAGMENTS.len())];
+    let edited = format!("{}{}{}", &src[..a], text, &src[b..]);
+    let edit = TextEdit::new(Span(a, b), text);
+    let _ = parse_incremental(&edited, &previous, &edit);
+    let _ = parse_incremental_with_dialect(&edited, &varnish, &edit, Dialect::Varnish);
+
+    // An edit which does not match the sources at all.
+    let a = rng.below(src.len() + 8);
+    let b = rng.below(src.len() + 8);
+    let _ = parse_incremental(src, &previous, &TextEdit::new(Span(a, b), text));
+    let _ = parse_incremental(&edited, &previous, &TextEdit::new(Span(b, a), ""));
+}
+
+/// Checks the sources generated from every seed, printing the failing one.
+fn run(generate: fn(&mut Rng) -> String) {
+    for seed in 1..=cases() {
+        let mut rng = Rng(seed);
+        let src = generate(&mut rng);
+        if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(|| check(&src, &mut rng))) {
+            eprintln!("seed {}: {:?}", seed, src);
+            panic::resume_unwind(panic);
+        }
+    }
+}
+
+#[test]
+fn arbitrary_input() {
+    run(arbitrary_bytes);
+}
+
+#[test]
+fn grammar_input() {
+    run(grammar_soup);
+}
+
+#[test]
+fn oversized_numbers() {
+    for value in &FRAGMENTS[FRAGMENTS.len() - 6..] {
+        for src in [
+            format!("backend b {{ .connect_timeout = {}; }}", value),
+            format!("table t RTIME {{ \"k\": {} }}", value),
+            format!("sub f {{ set req.http.X = {}; }}", value),
+        ] {
+            check(&src, &mut Rng(1));
+        }
+    }
+    let errors = parse("backend b { .connect_timeout = 170141183460469231731687303.715884ms; }")
+        .unwrap_err();
+    assert!(errors.iter().any(|e| e.code == ErrorCode::InvalidRTime));
+}
+
+/// Sources nesting `depth` levels of each construct which nests.
+fn nested(depth: usize) -> [String; 7] {
+    [
+        format!(
+            "sub f {{ set req.http.X = {}1{}; }}",
+            "(".repeat(depth),
+            ")".repeat(depth)
+        ),
+        format!(
+            "sub f {{ {}{} }}",
+            "if (a) {".repeat(depth),
+            "}".repeat(depth)
+        ),
+        format!("sub f {{ if (a) {{}} {}}}", "else if (a) {} ".repeat(depth)),
+        format!(
+            "backend b {{ .probe = {}{} }}",
+            "{ .x = ".repeat(depth),
+            "}".repeat(depth)
+        ),
+        format!("sub f {{ set req.http.X = {}a; }}", "!".repeat(depth)),
+        format!("sub f {{ set req.http.X = a{}; }}", " + a".repeat(depth)),
+        format!(
+            "sub f {{ set req.http.X = {}1{}; }}",
+            "f(".repeat(depth),
+            ")".repeat(depth)
+        ),
+    ]
+}
+
+#[test]
+fn deep_nesting() {
+    for src in nested(20) {
+        let (_, errors) = parse_recovering(&src);
+        assert!(
+            errors.iter().all(|e| e.code != ErrorCode::TooDeep),
+            "{}: {:?}",
+            src,
+            errors
+        );
+    }
+    for src in nested(100_000) {
+        let (_, errors) = parse_recovering(&src);
+        assert!(
+            errors.iter().any(|e| e.code == ErrorCode::TooDeep),
+            "{}: {:?}",
+            &src[..40],
+            errors
+        );
+        assert!(parse_lossless(&src).is_err());
+    }
+    // Juxtaposed strings are a flat list rather than a chain.
+    let src = format!("sub f {{ set req.http.X = a{}; }}", " a".repeat(100_000));
+    assert!(parse(&src).is_ok());
+}
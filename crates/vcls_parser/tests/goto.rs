@@ -0,0 +1,79 @@
+use vcls_ast::*;
+use vcls_parser::{parse, parse_recovering, ErrorCode};
+
+fn body(src: &str) -> Vec<Statement> {
+    match parse(src).unwrap().declarations.remove(0) {
+        Declaration::Subroutine(sub) => sub.body,
+        d => panic!("Unexpected declaration: {:?}", d),
+    }
+}
+
+#[test]
+fn goto_and_label() {
+    assert_eq!(
+        body("sub vcl_recv { goto done; done: }"),
+        vec![
+            Statement::Goto(GotoStatement {
+                label: Ident::new("done", Span(20, 24)),
+                span: Span(15, 25),
+            }),
+            Statement::Label(LabelStatement {
+                name: Ident::new("done", Span(26, 30)),
+                span: Span(26, 31),
+            }),
+        ]
+    );
+}
+
+#[test]
+fn nested() {
+    let body = body(
+        r#"sub vcl_recv {
+  if (req.http.X) {
+    goto skip;
+  } else {
+    set:
+    goto skip;
+  }
+  skip :
+  set req.http.Y = "1";
+}"#,
+    );
+    assert_eq!(body.len(), 3);
+    assert!(matches!(&body[1], Statement::Label(l) if l.name == "skip"));
+    assert!(matches!(&body[2], Statement::Set(_)));
+}
+
+#[test]
+fn invalid_targets() {
+    let src = r#"sub vcl_recv {
+  again:
+  goto again;
+  goto nowhere;
+  again:
+}
+sub vcl_fetch {
+  other:
+}"#;
+    let (_, errors) = parse_recovering(src);
+    let found: Vec<(ErrorCode, &str)> = errors
+        .iter()
+        .map(|e| (e.code, &src[e.span.0..e.span.1]))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (ErrorCode::DuplicateLabel, "again:"),
+            (ErrorCode::BackwardGoto, "goto again;"),
+            (ErrorCode::UnknownLabel, "nowhere"),
+        ]
+    );
+    assert_eq!(errors[1].related[0].span.0, src.find("again:").unwrap());
+}
+
+#[test]
+fn other_subroutine() {
+    let errors = parse("sub a { goto x; }\nsub b { x: }").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, ErrorCode::UnknownLabel);
+}
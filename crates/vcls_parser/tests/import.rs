@@ -7,7 +7,7 @@ fn import() {
         parse("import foo;").unwrap(),
         Vcl {
             declarations: vec![Declaration::Import(ImportDeclaration {
-                ident: "foo".to_string(),
+                ident: Ident::new("foo", Span(7, 10)),
                 span: Span(0, 11)
             })]
         }
@@ -8,7 +8,9 @@ fn include() {
         Vcl {
             declarations: vec![Declaration::Include(IncludeDeclaration {
                 path: StringToken {
+                    kind: StringKind::Quoted,
                     value: "foo.vcl".to_string(),
+                    raw: r#""foo.vcl""#.to_string(),
                     span: Span(8, 17),
                 },
                 span: Span(0, 18),
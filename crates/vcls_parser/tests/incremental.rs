@@ -0,0 +1,257 @@
+use vcls_ast::*;
+use vcls_parser::{
+    parse_incremental, parse_incremental_with_dialect, parse_recovering,
+    parse_recovering_with_dialect, Dialect, TextEdit,
+};
+
+const SRC: &str = r#"include "foo.vcl";
+acl internal {
+  "localhost";
+  !"192.168.0.0"/16;
+}
+backend origin { .host = "example.com"; .port = "443"; }
+table redirects STRING {
+  "/a": "/b",
+}
+sub vcl_recv {
+  if (req.http.X-Foo == "bar") {
+    set req.http.X-Bar = std.tolower(req.http.X-Foo) "baz";
+  } else {
+    return(pass);
+  }
+}
+/* trailing */
+sub vcl_deliver { restart; }
+"#;
+
+fn check(src: &str, edit: TextEdit) {
+    let previous = parse_recovering(src);
+    let edited = edit.apply(src);
+    assert_eq!(
+        parse_incremental(&edited, &previous, &edit),
+        parse_recovering(&edited),
+        "{:?} on {:?}",
+        edit,
+        src
+    );
+}
+
+#[test]
+fn edit_inside_declaration() {
+    let start = SRC.find("return(pass)").unwrap();
+    let edit = TextEdit::new(Span(start + 7, start + 11), "lookup");
+    let edited = edit.apply(SRC);
+    let (vcl, errors) = parse_incremental(&edited, &parse_recovering(SRC), &edit);
+    assert!(errors.is_empty());
+    assert_eq!(vcl, parse_recovering(&edited).0);
+    let Declaration::Subroutine(sub) = &vcl.declarations[5] else {
+        panic!("expected a subroutine");
+    };
+    assert_eq!(sub.span.1 - sub.span.0, "{ restart; }".len());
+}
+
+#[test]
+fn break_and_fix() {
+    let brace = SRC.find("} else").unwrap();
+    let edit = TextEdit::new(Span(brace, brace + 1), "");
+    let broken = edit.apply(SRC);
+    let previous = parse_recovering(SRC);
+    let (vcl, errors) = parse_incremental(&broken, &previous, &edit);
+    assert!(!errors.is_empty());
+    assert_eq!((vcl.clone(), errors.clone()), parse_recovering(&broken));
+
+    let fix = TextEdit::new(Span(brace, brace), "}");
+    assert_eq!(parse_incremental(SRC, &(vcl, errors), &fix), previous);
+}
+
+#[test]
+fn new_declaration() {
+    check(SRC, TextEdit::new(Span(0, 0), "import std;\n"));
+    check(
+        SRC,
+        TextEdit::new(Span(SRC.len(), SRC.len()), "penaltybox p {}"),
+    );
+    let end = SRC.find("}\ntable").unwrap() + 1;
+    check(SRC, TextEdit::new(Span(end, end), "ratecounter r {}"));
+}
+
+#[test]
+fn every_position() {
+    let broken = SRC
+        .replace("} else", "else")
+        .replace("\"localhost\";", "\"localhost\"");
+    for src in [SRC, &broken] {
+        for i in 0..=src.len() {
+            for text in [
+                "{",
+                "}",
+                "\"",
+                ";",
+                "x",
+                "\n",
+                "/*",
+                "sub",
+                "else",
+                "table t {",
+            ] {
+                check(src, TextEdit::new(Span(i, i), text));
+            }
+            if i < src.len() {
+                check(src, TextEdit::new(Span(i, i + 1), ""));
+            }
+        }
+    }
+}
+
+#[test]
+fn table_references() {
+    let src = "table t BACKEND { \"a\": origin }\nbackend origin { .host = \"example.com\"; }\n";
+    let name = src.rfind("origin").unwrap();
+    for edit in [
+        TextEdit::new(Span(name, name + 6), "other"),
+        TextEdit::new(Span(name + 6, name + 6), "2"),
+        TextEdit::new(Span(0, 0), "table u BACKEND { \"b\": other }\n"),
+    ] {
+        check(src, edit.clone());
+        let edited = edit.apply(src);
+        assert!(!parse_recovering(&edited).1.is_empty());
+        check(&edited, TextEdit::new(Span(0, 0), "\n"));
+    }
+}
+
+#[test]
+fn varnish() {
+    let src = "vcl 4.1;\nsub vcl_recv { return(pipe); }\nsub vcl_pipe { return(pipe); }\n";
+    let previous = parse_recovering_with_dialect(src, Dialect::Varnish);
+    assert!(previous.1.is_empty());
+    for at in [
+        src.find("return").unwrap(),
+        src.rfind("return").unwrap(),
+        0,
+        src.len(),
+    ] {
+        for edit in [
+            TextEdit::new(Span(at, at), " "),
+            TextEdit::new(Span(at, src.len() + 8), ""),
+        ] {
+            let edited = if edit.span.1 <= src.len() {
+                edit.apply(src)
+            } else {
+                src.to_string()
+            };
+            let (vcl, errors) =
+                parse_incremental_with_dialect(&edited, &previous, &edit, Dialect::Varnish);
+            assert!(errors.is_empty(), "{:?}", errors);
+            assert_eq!(
+                (vcl, errors),
+                parse_recovering_with_dialect(&edited, Dialect::Varnish)
+            );
+        }
+    }
+}
+
+const FRAGMENTS: [&str; 34] = [
+    "sub ",
+    "vcl_recv ",
+    "acl ",
+    "backend ",
+    "table t ",
+    "x ",
+    "{",
+    "}",
+    "{ ",
+    "} ",
+    "(",
+    ")",
+    ";",
+    "= 1; ",
+    ".host = \"h\"; ",
+    "\"k\": \"v\", ",
+    "\"10.0.0.0\"/8; ",
+    "set req.http.X = \"a\"; ",
+    "if (a) ",
+    "else ",
+    "goto l; ",
+    "l: ",
+    "return(pass); ",
+    "\"%zz\" ",
+    "{X\"",
+    "\"X} ",
+    "\"",
+    "# c\n",
+    "/* ",
+    "*/",
+    "\n",
+    " ",
+    "include \"x\";\n",
+    "import std;\n",
+];
+
+/// xorshift64*
+fn next(state: &mut u64) -> usize {
+    *state ^= *state >> 12;
+    *state ^= *state << 25;
+    *state ^= *state >> 27;
+    (state.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 33) as usize
+}
+
+fn soup(state: &mut u64, len: usize) -> String {
+    (0..next(state) % len)
+        .map(|_| FRAGMENTS[next(state) % FRAGMENTS.len()])
+        .collect()
+}
+
+/// Random edits give the same AST and diagnostics as parsing the edited source from scratch.
+#[test]
+fn differential() {
+    let cases = std::env::var("VCLS_FUZZ_CASES")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(2000u64);
+    for seed in 1..=cases {
+        let mut state = seed;
+        let mut src = soup(&mut state, 24);
+        let mut result = parse_recovering(&src);
+        for _ in 0..4 {
+            let boundaries: Vec<usize> = (0..=src.len())
+                .filter(|&i| src.is_char_boundary(i))
+                .collect();
+            let mut a = boundaries[next(&mut state) % boundaries.len()];
+            let mut b = boundaries[next(&mut state) % boundaries.len()];
+            if a > b {
+                std::mem::swap(&mut a, &mut b);
+            }
+            if next(&mut state).is_multiple_of(2) {
+                b = a;
+            }
+            let edit = TextEdit::new(Span(a, b), soup(&mut state, 3));
+            let edited = edit.apply(&src);
+            result = parse_incremental(&edited, &result, &edit);
+            assert_eq!(
+                result,
+                parse_recovering(&edited),
+                "seed {}: {:?} on {:?}",
+                seed,
+                edit,
+                src
+            );
+            src = edited;
+        }
+    }
+}
+
+#[test]
+fn diagnostics_outside_declarations() {
+    let src = "backend b { .host = \"h\"; }\nacl sub x {= 1; } }\n";
+    check(src, TextEdit::new(Span(27, 27), "backend"));
+    // The name taken by a broken subroutine is the keyword of the next declaration.
+    let src = "sub acl x;\nbackend b {}\nacl a {}\n";
+    check(src, TextEdit::new(Span(24, 24), " "));
+}
+
+#[test]
+fn heredoc_opened_before_edit() {
+    let src = "acl a { {X\"a\"; }\nbackend b {}\n";
+    let end = src.len();
+    check(src, TextEdit::new(Span(end, end), "\"X}"));
+}
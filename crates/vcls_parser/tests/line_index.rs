@@ -0,0 +1,59 @@
+use vcls_ast::Span;
+use vcls_parser::{parse, Encoding, LineCol, LineIndex};
+
+const SRC: &str = "sub vcl_recv {\r\n  set req.http.X = \"ä😀b\";\n}";
+
+#[test]
+fn line_col() {
+    let index = LineIndex::new(SRC);
+    assert_eq!(index.line_count(), 3);
+    assert_eq!(index.line_col(0, Encoding::Utf8), LineCol::new(0, 0));
+    assert_eq!(index.line_col(14, Encoding::Utf8), LineCol::new(0, 14));
+    assert_eq!(index.line_col(16, Encoding::Utf8), LineCol::new(1, 0));
+    let b = SRC.find("b\"").unwrap();
+    assert_eq!(index.line_col(b, Encoding::Utf8), LineCol::new(1, 26));
+    assert_eq!(index.line_col(b, Encoding::Utf16), LineCol::new(1, 23));
+    assert_eq!(
+        index.line_col(SRC.len(), Encoding::Utf16),
+        LineCol::new(2, 1)
+    );
+    assert_eq!(
+        index.line_col(SRC.len() + 10, Encoding::Utf8),
+        LineCol::new(2, 1)
+    );
+}
+
+#[test]
+fn offset() {
+    let index = LineIndex::new(SRC);
+    for offset in SRC.char_indices().map(|(i, _)| i).chain([SRC.len()]) {
+        for encoding in [Encoding::Utf8, Encoding::Utf16] {
+            let pos = index.line_col(offset, encoding);
+            assert_eq!(index.offset(pos, encoding), Some(offset), "{:?}", pos);
+        }
+    }
+    // Inside `😀`, which is two UTF-16 code units and four bytes.
+    let emoji = index.line_col(SRC.find('😀').unwrap(), Encoding::Utf16);
+    assert_eq!(
+        index.offset(LineCol::new(1, emoji.col + 1), Encoding::Utf16),
+        None
+    );
+    assert_eq!(index.offset(LineCol::new(0, 16), Encoding::Utf8), None);
+    assert_eq!(index.offset(LineCol::new(3, 0), Encoding::Utf8), None);
+}
+
+#[test]
+fn span() {
+    let vcl = parse(SRC).unwrap();
+    let index = LineIndex::new(SRC);
+    let span = vcl.declarations[0].span();
+    let (start, end) = index.range(span, Encoding::Utf16);
+    assert_eq!((start, end), (LineCol::new(0, 13), LineCol::new(2, 1)));
+    assert_eq!(start.to_string(), "1:14");
+    assert_eq!(index.span(start, end, Encoding::Utf16), Some(span));
+    assert_eq!(index.span(end, start, Encoding::Utf16), None);
+    assert_eq!(
+        index.span(LineCol::new(1, 2), LineCol::new(1, 5), Encoding::Utf8),
+        Some(Span(18, 21))
+    );
+}
@@ -0,0 +1,84 @@
+use vcls_ast::*;
+use vcls_parser::{parse, ErrorCode};
+
+fn value(number: &str) -> Literal {
+    let src = format!("table t FLOAT {{ \"k\": {} }}", number);
+    match parse(&src).unwrap().declarations.remove(0) {
+        Declaration::Table(mut table) => match table.entries.remove(0).value {
+            TableValue::Literal(literal) => literal,
+            v => panic!("Unexpected value: {:?}", v),
+        },
+        d => panic!("Unexpected declaration: {:?}", d),
+    }
+}
+
+fn integer(number: &str) -> i64 {
+    match value(number) {
+        Literal::Integer(i) => {
+            assert_eq!(i.raw, number);
+            i.value
+        }
+        l => panic!("Unexpected literal: {:?}", l),
+    }
+}
+
+fn float(number: &str) -> f64 {
+    match value(number) {
+        Literal::Float(f) => {
+            assert_eq!(f.raw, number);
+            f.value
+        }
+        l => panic!("Unexpected literal: {:?}", l),
+    }
+}
+
+#[test]
+fn decimal() {
+    assert_eq!(integer("0"), 0);
+    assert_eq!(integer("42"), 42);
+    assert_eq!(integer("-42"), -42);
+    assert_eq!(integer("9223372036854775807"), i64::MAX);
+    assert_eq!(integer("-9223372036854775808"), i64::MIN);
+    assert_eq!(float("1.5"), 1.5);
+    assert_eq!(float("-0.25"), -0.25);
+}
+
+#[test]
+fn exponent() {
+    assert_eq!(float("1e5"), 100000.0);
+    assert_eq!(float("2.5e-3"), 0.0025);
+    assert_eq!(float("1e+2"), 100.0);
+}
+
+#[test]
+fn hexadecimal() {
+    assert_eq!(integer("0x1F"), 31);
+    assert_eq!(integer("-0xff"), -255);
+    assert_eq!(integer("0x7FFFFFFFFFFFFFFF"), i64::MAX);
+    assert_eq!(integer("-0x8000000000000000"), i64::MIN);
+    assert_eq!(float("0x1.8p3"), 12.0);
+    assert_eq!(float("0x10p-2"), 4.0);
+    assert_eq!(float("0xA.8"), 10.5);
+}
+
+#[test]
+fn overflow() {
+    for (src, number) in [
+        (
+            "table t FLOAT { \"k\": 9223372036854775808 }",
+            "9223372036854775808",
+        ),
+        (
+            "table t FLOAT { \"k\": -0x8000000000000001 }",
+            "-0x8000000000000001",
+        ),
+        ("table t FLOAT { \"k\": 1e999 }", "1e999"),
+    ] {
+        let errors = parse(src).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, ErrorCode::InvalidNumber);
+        assert_eq!(errors[0].span, Span(21, 21 + number.len()));
+        assert!(errors[0].message.contains(number), "{}", errors[0].message);
+        assert_eq!(errors[0].notes.len(), 1);
+    }
+}
@@ -7,7 +7,7 @@ fn penaltybox() {
         parse("penaltybox test { }").unwrap(),
         Vcl {
             declarations: vec![Declaration::PenaltyBox(PenaltyBoxDeclaration {
-                name: "test".to_string(),
+                name: Ident::new("test", Span(11, 15)),
                 span: Span(0, 19)
             })]
         }
@@ -7,7 +7,7 @@ fn ratecounter() {
         parse("ratecounter test { }").unwrap(),
         Vcl {
             declarations: vec![Declaration::RateCounter(RateCounterDeclaration {
-                name: "test".to_string(),
+                name: Ident::new("test", Span(12, 16)),
                 span: Span(0, 20)
             })]
         }
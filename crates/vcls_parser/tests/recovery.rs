@@ -0,0 +1,130 @@
+use vcls_ast::*;
+use vcls_parser::{parse, parse_recovering, ErrorCode};
+
+#[test]
+fn valid_source() {
+    let src = r#"acl foo { "localhost"; }"#;
+    let (vcl, errors) = parse_recovering(src);
+    assert!(errors.is_empty());
+    assert_eq!(vcl, parse(src).unwrap());
+}
+
+#[test]
+fn broken_declaration() {
+    let (vcl, errors) = parse_recovering(
+        r#"acl foo { "localhost" }
+backend bar {}
+"#,
+    );
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, ErrorCode::Syntax);
+    assert_eq!(errors[0].span, Span(22, 22));
+    assert_eq!(
+        vcl,
+        Vcl {
+            declarations: vec![
+                Declaration::Invalid(InvalidDeclaration { span: Span(0, 24) }),
+                Declaration::Backend(BackendDeclaration {
+                    name: Ident::new("bar", Span(32, 35)),
+                    config: Object {
+                        entries: vec![],
+                        span: Span(36, 38),
+                    },
+                    settings: Box::default(),
+                    span: Span(24, 38),
+                }),
+            ],
+        },
+    );
+}
+
+#[test]
+fn broken_statement() {
+    let (vcl, errors) = parse_recovering(
+        r#"sub foo {
+  set req.http.X = ;
+  if (req.http.Y) { set = 1; } else { }
+  restart;
+}
+sub bar {}"#,
+    );
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].span, Span(29, 29));
+    assert_eq!(errors[1].span, Span(55, 55));
+    assert_eq!(
+        vcl,
+        Vcl {
+            declarations: vec![
+                Declaration::Subroutine(SubroutineDeclaration {
+                    name: Ident::new("foo", Span(4, 7)),
+                    return_type: Type::Void,
+                    body: vec![
+                        Statement::Invalid(InvalidStatement { span: Span(12, 30) }),
+                        Statement::Invalid(InvalidStatement { span: Span(33, 70) }),
+                        Statement::Restart(RestartStatement { span: Span(73, 81) }),
+                    ],
+                    span: Span(8, 83),
+                }),
+                Declaration::Subroutine(SubroutineDeclaration {
+                    name: Ident::new("bar", Span(88, 91)),
+                    return_type: Type::Void,
+                    body: vec![],
+                    span: Span(92, 94),
+                }),
+            ],
+        },
+    );
+}
+
+#[test]
+fn unclosed_sub() {
+    let (vcl, errors) = parse_recovering(
+        r#"sub foo {
+  restart;
+sub bar {}"#,
+    );
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "Unclosed subroutine body");
+    assert_eq!(errors[0].span, Span(8, 9));
+    assert_eq!(vcl.declarations.len(), 2);
+    assert!(matches!(
+        &vcl.declarations[0],
+        Declaration::Subroutine(SubroutineDeclaration { body, .. }) if body.len() == 1
+    ));
+    assert!(matches!(
+        &vcl.declarations[1],
+        Declaration::Subroutine(SubroutineDeclaration { name, .. }) if name == "bar"
+    ));
+}
+
+#[test]
+fn garbage() {
+    let (vcl, errors) = parse_recovering("}}} ;; \"unterminated");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        vcl.declarations,
+        vec![Declaration::Invalid(InvalidDeclaration {
+            span: Span(0, 20)
+        })]
+    );
+}
+
+#[test]
+fn diagnostics_keep_parsed_nodes() {
+    let (vcl, errors) = parse_recovering(
+        r#"backend foo { .hots = "example.com"; }
+sub vcl_recv { set req.http.a = "%zz"; }
+"#,
+    );
+    let codes: Vec<ErrorCode> = errors.iter().map(|e| e.code).collect();
+    assert_eq!(
+        codes,
+        [ErrorCode::UnknownProperty, ErrorCode::InvalidEscape]
+    );
+    let [Declaration::Backend(backend), Declaration::Subroutine(sub)] = vcl.declarations.as_slice()
+    else {
+        panic!("Unexpected declarations: {:?}", vcl.declarations);
+    };
+    assert_eq!(backend.name, "foo");
+    assert!(matches!(sub.body.as_slice(), [Statement::Set(_)]));
+}
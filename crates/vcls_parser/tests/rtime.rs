@@ -0,0 +1,71 @@
+use vcls_ast::*;
+use vcls_parser::{parse, ErrorCode};
+
+fn rtime(value: &str) -> RelativeTime {
+    let src = format!("table t RTIME {{ \"k\": {} }}", value);
+    match parse(&src).unwrap().declarations.remove(0) {
+        Declaration::Table(mut table) => match table.entries.remove(0).value {
+            TableValue::Literal(Literal::RTime(rtime)) => rtime.value,
+            v => panic!("Unexpected value: {:?}", v),
+        },
+        d => panic!("Unexpected declaration: {:?}", d),
+    }
+}
+
+#[test]
+fn signed_and_fractional() {
+    assert_eq!(rtime("-1s"), RelativeTime::from_sec(-1.0));
+    assert!(rtime("-1s").is_negative());
+    assert_eq!(rtime("0.5ms").as_nanos(), 500_000);
+    assert_eq!(rtime("1.5h"), RelativeTime::from_min(90.0));
+    assert_eq!(rtime("0.1s").as_nanos(), 100_000_000);
+    assert_eq!(rtime("2d").as_secs(), 172_800.0);
+    assert_eq!(rtime("250ms").as_ms(), 250.0);
+}
+
+#[test]
+fn display() {
+    for (value, canonical) in [
+        ("90s", "90s"),
+        ("3600s", "1h"),
+        ("60m", "1h"),
+        ("1000ms", "1s"),
+        ("0.5ms", "0.5ms"),
+        ("1.25ms", "1.25ms"),
+        ("-2.5s", "-2500ms"),
+        ("24h", "1d"),
+        ("365d", "1y"),
+        ("0s", "0s"),
+    ] {
+        assert_eq!(rtime(value).to_string(), canonical, "{}", value);
+        assert_eq!(canonical.parse(), Ok(rtime(value)));
+    }
+}
+
+#[test]
+fn checked_arithmetic() {
+    let second = RelativeTime::from_sec(1.0);
+    assert_eq!(second - second * 2, RelativeTime::from_sec(-1.0));
+    assert_eq!(second.checked_div(0), None);
+    assert_eq!(RelativeTime::from_nanos(i64::MAX).checked_add(second), None);
+    assert_eq!(RelativeTime::from_nanos(i64::MIN).checked_neg(), None);
+    assert_eq!(second.checked_sub(second), Some(RelativeTime::ZERO));
+    let max = RelativeTime::from_nanos(i64::MAX);
+    assert_eq!(max + second, max);
+    assert_eq!(-RelativeTime::from_nanos(i64::MIN), max);
+    assert_eq!(second / 0, max);
+    assert_eq!(-second / 0, RelativeTime::from_nanos(i64::MIN));
+}
+
+#[test]
+fn out_of_range() {
+    let errors = parse("table t RTIME { \"k\": 300y }").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, ErrorCode::InvalidRTime);
+    assert_eq!(errors[0].span, Span(21, 25));
+    assert_eq!("x1s".parse::<RelativeTime>(), Err(()));
+    assert_eq!("1w".parse::<RelativeTime>(), Err(()));
+    let src = "backend b { .connect_timeout = 170141183460469231731687303.715884ms; }";
+    let errors = parse(src).unwrap_err();
+    assert!(errors.iter().any(|e| e.code == ErrorCode::InvalidRTime));
+}
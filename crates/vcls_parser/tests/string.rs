@@ -0,0 +1,115 @@
+use vcls_ast::*;
+use vcls_parser::{parse, parse_recovering, ErrorCode, Severity};
+
+fn tokens(value: &str) -> Vec<StringToken> {
+    let src = format!("table t {{ \"k\": {} }}", value);
+    match parse(&src).unwrap().declarations.remove(0) {
+        Declaration::Table(mut table) => match table.entries.remove(0).value {
+            TableValue::Literal(Literal::String(s)) => s.tokens,
+            v => panic!("Unexpected value: {:?}", v),
+        },
+        d => panic!("Unexpected declaration: {:?}", d),
+    }
+}
+
+#[test]
+fn escapes() {
+    let token = &tokens(r#""a%22b%0A%u00E4%u{1F600}%C3%A4""#)[0];
+    assert_eq!(token.kind, StringKind::Quoted);
+    assert_eq!(token.value, "a\"b\nä😀ä");
+    assert_eq!(token.raw, r#""a%22b%0A%u00E4%u{1F600}%C3%A4""#);
+}
+
+#[test]
+fn long_strings_are_verbatim() {
+    let tokens = tokens(r#"{"a%22"} {EOF"%zz"EOF}"#);
+    assert_eq!(tokens[0].kind, StringKind::Braces);
+    assert_eq!(tokens[0].value, "a%22");
+    assert_eq!(tokens[0].raw, r#"{"a%22"}"#);
+    assert_eq!(
+        tokens[1].kind,
+        StringKind::Heredoc {
+            delimiter: "EOF".to_string()
+        }
+    );
+    assert_eq!(tokens[1].value, "%zz");
+}
+
+#[test]
+fn malformed_escapes() {
+    let src = r#"table t { "k": "%zz %u12 %u{110000} %uD800 %u{} %FF" }"#;
+    let (_, errors) = parse_recovering(src);
+    let found: Vec<(&str, ErrorCode)> = errors
+        .iter()
+        .map(|e| (&src[e.span.0..e.span.1], e.code))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("%zz", ErrorCode::InvalidEscape),
+            ("%u12", ErrorCode::InvalidEscape),
+            ("%u{110000}", ErrorCode::InvalidEscape),
+            ("%uD800", ErrorCode::InvalidEscape),
+            ("%u{", ErrorCode::InvalidEscape),
+            (
+                r#""%zz %u12 %u{110000} %uD800 %u{} %FF""#,
+                ErrorCode::InvalidEscape
+            ),
+        ]
+    );
+    assert!(errors[0].message.contains("`%zz`"));
+    assert_eq!(errors[0].severity, Severity::Warning);
+    assert!(errors[1..].iter().all(|e| e.severity == Severity::Error));
+}
+
+#[test]
+fn stray_percent() {
+    let token = &tokens(r#""100% %zz %4""#)[0];
+    assert_eq!(token.value, "100% %zz %4");
+
+    let src = r#"table t { "k": "100%" }"#;
+    let (_, errors) = parse_recovering(src);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, ErrorCode::InvalidEscape);
+    assert_eq!(errors[0].severity, Severity::Warning);
+    assert_eq!(&src[errors[0].span.0..errors[0].span.1], "%");
+}
+
+#[test]
+fn include_path() {
+    let vcl = parse(r#"include "my%20file";"#).unwrap();
+    let Declaration::Include(include) = &vcl.declarations[0] else {
+        panic!("expected an include");
+    };
+    assert_eq!(include.path.value, "my file");
+    assert_eq!(include.path.raw, r#""my%20file""#);
+}
+
+#[test]
+fn kinds() {
+    let tokens = tokens(r#""" "a" {""} {X""X} {X"a"X}"#);
+    let kinds: Vec<&StringKind> = tokens.iter().map(|t| &t.kind).collect();
+    let heredoc = StringKind::Heredoc {
+        delimiter: "X".to_string(),
+    };
+    assert_eq!(
+        kinds,
+        [
+            &StringKind::Empty,
+            &StringKind::Quoted,
+            &StringKind::Empty,
+            &StringKind::Empty,
+            &heredoc
+        ]
+    );
+    assert_eq!(tokens[2].raw, r#"{""}"#);
+    assert_eq!(tokens[3].raw, r#"{X""X}"#);
+    let values: Vec<&str> = tokens.iter().map(|t| t.value.as_str()).collect();
+    assert_eq!(values, ["", "a", "", "", "a"]);
+}
+
+#[test]
+fn unterminated_heredoc() {
+    let (_, errors) = parse_recovering(r#"table t { "k": {X"X} }"#);
+    assert!(!errors.is_empty());
+}
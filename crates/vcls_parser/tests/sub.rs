@@ -1,5 +1,5 @@
 use vcls_ast::*;
-use vcls_parser::parse;
+use vcls_parser::{parse, ErrorCode};
 
 #[test]
 fn ret() {
@@ -7,7 +7,7 @@ fn ret() {
         parse("sub foo { return /* noop */; }").unwrap(),
         Vcl {
             declarations: vec![Declaration::Subroutine(SubroutineDeclaration {
-                name: "foo".to_string(),
+                name: Ident::new("foo", Span(4, 7)),
                 return_type: Type::Void,
                 body: vec![Statement::Return(ReturnStatement {
                     value: None,
@@ -25,26 +25,31 @@ fn ret_value() {
         parse(r#"sub foo STRING { return "foo" with_variable.bar:baz; }"#).unwrap(),
         Vcl {
             declarations: vec![Declaration::Subroutine(SubroutineDeclaration {
-                name: "foo".to_string(),
+                name: Ident::new("foo", Span(4, 7)),
                 return_type: Type::String,
                 body: vec![Statement::Return(ReturnStatement {
-                    value: Some(Expression::Binary(BinaryExpression {
-                        lhs: Box::new(Expression::Literal(Literal::String(StringLiteral {
-                            tokens: vec![StringToken {
-                                value: "foo".to_string(),
-                                span: Span(24, 29),
-                            }],
-                            span: Span(24, 30),
-                        }))),
-                        operator: BinaryOperator::Add,
-                        rhs: Box::new(Expression::Variable(Variable {
-                            name: "with_variable".to_string(),
-                            properties: vec!["bar".to_string()],
-                            sub_field: Some("baz".to_string()),
-                            span: Span(30, 51),
-                        })),
-                        span: Span(24, 51),
-                    })),
+                    value: Some(ReturnValue::Expression(Expression::Concat(
+                        ConcatExpression {
+                            parts: vec![
+                                Expression::Literal(Literal::String(StringLiteral {
+                                    tokens: vec![StringToken {
+                                        kind: StringKind::Quoted,
+                                        value: "foo".to_string(),
+                                        raw: r#""foo""#.to_string(),
+                                        span: Span(24, 29),
+                                    }],
+                                    span: Span(24, 30),
+                                })),
+                                Expression::Variable(Variable {
+                                    name: "with_variable".to_string(),
+                                    properties: vec!["bar".to_string()],
+                                    sub_field: Some("baz".to_string()),
+                                    span: Span(30, 51),
+                                }),
+                            ],
+                            span: Span(24, 51),
+                        }
+                    ))),
                     span: Span(24, 51),
                 })],
                 span: Span(15, 54),
@@ -53,6 +58,45 @@ fn ret_value() {
     );
 }
 
+#[test]
+fn ret_action() {
+    let vcl =
+        parse("sub vcl_recv { return(pass); return /* c */ lookup ; return ( deliver_stale ); }")
+            .unwrap();
+    let Declaration::Subroutine(sub) = &vcl.declarations[0] else {
+        panic!("Unexpected declaration: {:?}", vcl.declarations[0]);
+    };
+    assert_eq!(
+        sub.body,
+        vec![
+            Statement::Return(ReturnStatement {
+                value: Some(ReturnValue::Action(ReturnAction::Pass)),
+                span: Span(21, 27),
+            }),
+            Statement::Return(ReturnStatement {
+                value: Some(ReturnValue::Action(ReturnAction::Lookup)),
+                span: Span(44, 51),
+            }),
+            Statement::Return(ReturnStatement {
+                value: Some(ReturnValue::Action(ReturnAction::DeliverStale)),
+                span: Span(60, 77),
+            }),
+        ]
+    );
+}
+
+#[test]
+fn ret_unknown_action() {
+    let src = "sub vcl_recv { return(nope); }";
+    let errors = parse(src).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, ErrorCode::UnknownReturnAction);
+    assert_eq!(&src[errors[0].span.0..errors[0].span.1], "nope");
+    let errors = parse("sub vcl_recv { return \"x\"; }").unwrap_err();
+    assert_eq!(errors[0].code, ErrorCode::Syntax);
+    assert_eq!(errors[0].expected, vec!["ReturnAction"]);
+}
+
 #[test]
 fn synthetic() {
     assert_eq!(
@@ -65,14 +109,16 @@ fn synthetic() {
         .unwrap(),
         Vcl {
             declarations: vec![Declaration::Subroutine(SubroutineDeclaration {
-                name: "vcl_error".to_string(),
+                name: Ident::new("vcl_error", Span(4, 13)),
                 return_type: Type::Void,
                 body: vec![
                     Statement::Synthetic(SyntheticStatement {
                         base64: true,
                         value: Expression::Literal(Literal::String(StringLiteral {
                             tokens: vec![StringToken {
+                                kind: StringKind::Quoted,
                                 value: "dGVzdA==".to_string(),
+                                raw: r#""dGVzdA==""#.to_string(),
                                 span: Span(49, 59),
                             }],
                             span: Span(49, 59),
@@ -83,7 +129,9 @@ fn synthetic() {
                         base64: false,
                         value: Expression::Literal(Literal::String(StringLiteral {
                             tokens: vec![StringToken {
+                                kind: StringKind::Quoted,
                                 value: "foo".to_string(),
+                                raw: r#""foo""#.to_string(),
                                 span: Span(87, 92)
                             }],
                             span: Span(87, 92)
@@ -110,7 +158,7 @@ fn if_set() {
         .unwrap(),
         Vcl {
             declarations: vec![Declaration::Subroutine(SubroutineDeclaration {
-                name: "vcl_recv".to_string(),
+                name: Ident::new("vcl_recv", Span(4, 12)),
                 return_type: Type::Void,
                 body: vec![Statement::If(IfStatement {
                     condition: Expression::Binary(BinaryExpression {
@@ -121,14 +169,17 @@ fn if_set() {
                             span: Span(35, 49),
                         })),
                         operator: BinaryOperator::Eq,
+                        operator_span: Span(49, 51),
                         rhs: Box::new(Expression::Literal(Literal::String(StringLiteral {
                             tokens: vec![StringToken {
+                                kind: StringKind::Quoted,
                                 value: "www.example.com".to_string(),
+                                raw: r#""www.example.com""#.to_string(),
                                 span: Span(52, 69)
                             }],
                             span: Span(52, 69)
                         }))),
-                        span: Span(49, 51),
+                        span: Span(35, 69),
                     }),
                     body: vec![Statement::Set(SetStatement {
                         target: Variable {
@@ -171,7 +222,7 @@ fn if_else() {
         .unwrap(),
         Vcl {
             declarations: vec![Declaration::Subroutine(SubroutineDeclaration {
-                name: "vcl_recv".to_string(),
+                name: Ident::new("vcl_recv", Span(4, 12)),
                 return_type: Type::Void,
                 body: vec![Statement::If(IfStatement {
                     // if (req.http.host == "www.example.com") {}
@@ -183,14 +234,17 @@ fn if_else() {
                             span: Span(35, 49),
                         })),
                         operator: BinaryOperator::Eq,
+                        operator_span: Span(49, 51),
                         rhs: Box::new(Expression::Literal(Literal::String(StringLiteral {
                             tokens: vec![StringToken {
+                                kind: StringKind::Quoted,
                                 value: "www.example.com".to_string(),
+                                raw: r#""www.example.com""#.to_string(),
                                 span: Span(52, 69),
                             }],
                             span: Span(52, 69),
                         }))),
-                        span: Span(49, 51),
+                        span: Span(35, 69),
                     }),
                     body: vec![],
                     els: Some(ElseStatement::If(Box::new(IfStatement {
@@ -203,14 +257,17 @@ fn if_else() {
                                 span: Span(96, 110),
                             })),
                             operator: BinaryOperator::Tilde,
+                            operator_span: Span(110, 111),
                             rhs: Box::new(Expression::Literal(Literal::String(StringLiteral {
                                 tokens: vec![StringToken {
+                                    kind: StringKind::Quoted,
                                     value: ".*\\.example\\.com".to_string(),
+                                    raw: r#"".*\.example\.com""#.to_string(),
                                     span: Span(112, 130),
                                 }],
                                 span: Span(112, 130),
                             }))),
-                            span: Span(110, 111),
+                            span: Span(96, 130),
                         }),
                         body: vec![],
                         els: Some(ElseStatement::If(Box::new(IfStatement {
@@ -223,16 +280,19 @@ fn if_else() {
                                     span: Span(158, 172),
                                 })),
                                 operator: BinaryOperator::Eq,
+                                operator_span: Span(172, 174),
                                 rhs: Box::new(Expression::Literal(Literal::String(
                                     StringLiteral {
                                         tokens: vec![StringToken {
+                                            kind: StringKind::Quoted,
                                             value: "example.com".to_string(),
+                                            raw: r#""example.com""#.to_string(),
                                             span: Span(175, 188),
                                         }],
                                         span: Span(175, 188),
                                     }
                                 ))),
-                                span: Span(172, 174),
+                                span: Span(158, 188),
                             }),
                             body: vec![],
                             els: Some(ElseStatement::If(Box::new(IfStatement {
@@ -245,16 +305,19 @@ fn if_else() {
                                         span: Span(217, 231),
                                     })),
                                     operator: BinaryOperator::NotTilde,
+                                    operator_span: Span(231, 233),
                                     rhs: Box::new(Expression::Literal(Literal::String(
                                         StringLiteral {
                                             tokens: vec![StringToken {
+                                                kind: StringKind::Quoted,
                                                 value: "example\\.com".to_string(),
+                                                raw: r#""example\.com""#.to_string(),
                                                 span: Span(234, 248),
                                             }],
                                             span: Span(234, 248),
                                         }
                                     ))),
-                                    span: Span(231, 233),
+                                    span: Span(217, 248),
                                 }),
                                 body: vec![],
                                 els: Some(ElseStatement::If(Box::new(IfStatement {
@@ -270,16 +333,19 @@ fn if_else() {
                                             span: Span(278, 292),
                                         })),
                                         operator: BinaryOperator::Ne,
+                                        operator_span: Span(292, 294),
                                         rhs: Box::new(Expression::Literal(Literal::String(
                                             StringLiteral {
                                                 tokens: vec![StringToken {
+                                                    kind: StringKind::Quoted,
                                                     value: "example.com".to_string(),
+                                                    raw: r#""example.com""#.to_string(),
                                                     span: Span(295, 308),
                                                 }],
                                                 span: Span(295, 308),
                                             }
                                         ))),
-                                        span: Span(292, 294),
+                                        span: Span(278, 308),
                                     }),
                                     body: vec![],
                                     els: Some(ElseStatement::Body(vec![])),
@@ -326,7 +392,7 @@ fn set() {
         .unwrap(),
         Vcl {
             declarations: vec![Declaration::Subroutine(SubroutineDeclaration {
-                name: "vcl_recv".to_string(),
+                name: Ident::new("vcl_recv", Span(4, 12)),
                 return_type: Type::Void,
                 body: vec![
                     Statement::Declare(DeclareStatement {
@@ -349,6 +415,7 @@ fn set() {
                         operator: SetOperator::Set,
                         value: Expression::Literal(Literal::Integer(IntegerLiteral {
                             value: 0,
+                            raw: "0".to_string(),
                             span: Span(94, 95)
                         })),
                         span: Span(79, 96),
@@ -363,6 +430,7 @@ fn set() {
                         operator: SetOperator::Add,
                         value: Expression::Literal(Literal::Integer(IntegerLiteral {
                             value: 2,
+                            raw: "2".to_string(),
                             span: Span(129, 130),
                         })),
                         span: Span(113, 131),
@@ -377,6 +445,7 @@ fn set() {
                         operator: SetOperator::Sub,
                         value: Expression::Literal(Literal::Integer(IntegerLiteral {
                             value: 1,
+                            raw: "1".to_string(),
                             span: Span(164, 165),
                         })),
                         span: Span(148, 166),
@@ -391,6 +460,7 @@ fn set() {
                         operator: SetOperator::Mul,
                         value: Expression::Literal(Literal::Integer(IntegerLiteral {
                             value: 4,
+                            raw: "4".to_string(),
                             span: Span(199, 200)
                         })),
                         span: Span(183, 201),
@@ -405,6 +475,7 @@ fn set() {
                         operator: SetOperator::Div,
                         value: Expression::Literal(Literal::Integer(IntegerLiteral {
                             value: 2,
+                            raw: "2".to_string(),
                             span: Span(234, 235),
                         })),
                         span: Span(218, 236),
@@ -419,6 +490,7 @@ fn set() {
                         operator: SetOperator::Mod,
                         value: Expression::Literal(Literal::Integer(IntegerLiteral {
                             value: 3,
+                            raw: "3".to_string(),
                             span: Span(269, 270),
                         })),
                         span: Span(253, 271),
@@ -433,6 +505,7 @@ fn set() {
                         operator: SetOperator::Bar,
                         value: Expression::Literal(Literal::Integer(IntegerLiteral {
                             value: 1,
+                            raw: "1".to_string(),
                             span: Span(304, 305),
                         })),
                         span: Span(288, 306),
@@ -447,6 +520,7 @@ fn set() {
                         operator: SetOperator::Amp,
                         value: Expression::Literal(Literal::Integer(IntegerLiteral {
                             value: 1,
+                            raw: "1".to_string(),
                             span: Span(339, 340),
                         })),
                         span: Span(323, 341),
@@ -461,6 +535,7 @@ fn set() {
                         operator: SetOperator::Hat,
                         value: Expression::Literal(Literal::Integer(IntegerLiteral {
                             value: 2,
+                            raw: "2".to_string(),
                             span: Span(374, 375),
                         })),
                         span: Span(358, 376),
@@ -475,6 +550,7 @@ fn set() {
                         operator: SetOperator::LShift,
                         value: Expression::Literal(Literal::Integer(IntegerLiteral {
                             value: 1,
+                            raw: "1".to_string(),
                             span: Span(410, 411),
                         })),
                         span: Span(393, 412),
@@ -489,6 +565,7 @@ fn set() {
                         operator: SetOperator::RShift,
                         value: Expression::Literal(Literal::Integer(IntegerLiteral {
                             value: 1,
+                            raw: "1".to_string(),
                             span: Span(446, 447),
                         })),
                         span: Span(429, 448),
@@ -503,6 +580,7 @@ fn set() {
                         operator: SetOperator::Ror,
                         value: Expression::Literal(Literal::Integer(IntegerLiteral {
                             value: 1,
+                            raw: "1".to_string(),
                             span: Span(483, 484),
                         })),
                         span: Span(465, 485),
@@ -517,6 +595,7 @@ fn set() {
                         operator: SetOperator::Rol,
                         value: Expression::Literal(Literal::Integer(IntegerLiteral {
                             value: 1,
+                            raw: "1".to_string(),
                             span: Span(520, 521),
                         })),
                         span: Span(502, 522),
@@ -531,6 +610,7 @@ fn set() {
                         operator: SetOperator::AmpAmp,
                         value: Expression::Literal(Literal::Integer(IntegerLiteral {
                             value: 1,
+                            raw: "1".to_string(),
                             span: Span(556, 557),
                         })),
                         span: Span(539, 558),
@@ -545,6 +625,7 @@ fn set() {
                         operator: SetOperator::BarBar,
                         value: Expression::Literal(Literal::Integer(IntegerLiteral {
                             value: 1,
+                            raw: "1".to_string(),
                             span: Span(592, 593),
                         })),
                         span: Span(575, 594),
@@ -581,7 +662,7 @@ fn error() {
         .unwrap(),
         Vcl {
             declarations: vec![Declaration::Subroutine(SubroutineDeclaration {
-                name: "vcl_recv".to_string(),
+                name: Ident::new("vcl_recv", Span(4, 12)),
                 return_type: Type::Void,
                 body: vec![
                     Statement::Esi(EsiStatement { span: Span(31, 35) }),
@@ -597,11 +678,14 @@ fn error() {
                     Statement::Error(ErrorStatement {
                         status: Some(Expression::Literal(Literal::Integer(IntegerLiteral {
                             value: 503,
+                            raw: "503".to_string(),
                             span: Span(84, 87)
                         }))),
                         message: Some(Expression::Literal(Literal::String(StringLiteral {
                             tokens: vec![StringToken {
+                                kind: StringKind::Quoted,
                                 value: "foo".to_string(),
+                                raw: r#""foo""#.to_string(),
                                 span: Span(88, 93)
                             }],
                             span: Span(88, 93)
@@ -644,11 +728,13 @@ fn include() {
         .unwrap(),
         Vcl {
             declarations: vec![Declaration::Subroutine(SubroutineDeclaration {
-                name: "vcl_recv".to_string(),
+                name: Ident::new("vcl_recv", Span(4, 12)),
                 return_type: Type::Void,
                 body: vec![Statement::Include(IncludeStatement {
                     path: StringToken {
+                        kind: StringKind::Quoted,
                         value: "foo.vcl".to_string(),
+                        raw: r#""foo.vcl""#.to_string(),
                         span: Span(39, 48),
                     },
                     span: Span(31, 49),
@@ -670,14 +756,14 @@ fn log() {
     ).unwrap(),
         Vcl {
             declarations: vec![Declaration::Subroutine(SubroutineDeclaration {
-                name: "vcl_deliver".to_string(),
+                name: Ident::new("vcl_deliver", Span(4, 15)),
                 return_type: Type::Void,
                 body: vec![
                     Statement::Log(LogStatement {
                         message: Expression::Literal(Literal::String(
                             StringLiteral{
                                 tokens: vec![
-                                    StringToken { value: "foo".to_string(), span: Span(38,43) }
+                                    StringToken { kind: StringKind::Quoted, value: "foo".to_string(), raw: r#""foo""#.to_string(), span: Span(38,43) }
                                 ],
                                 span: Span(38,43),
                             }
@@ -692,7 +778,7 @@ fn log() {
                             span: Span(65,86),
                         },
                         value: Expression::Literal(Literal::String(StringLiteral {
-                            tokens: vec![StringToken { value: "myCookie=foo; path=/; SameSite=Strict; Secure; max-age=60".to_string(), span: Span(88, 147),}],
+                            tokens: vec![StringToken { kind: StringKind::Quoted, value: "myCookie=foo; path=/; SameSite=Strict; Secure; max-age=60".to_string(), raw: r#""myCookie=foo; path=/; SameSite=Strict; Secure; max-age=60""#.to_string(), span: Span(88, 147),}],
                             span: Span(88, 147),
                         })),
                         span: Span(61,148),
@@ -703,3 +789,179 @@ fn log() {
         },
     )
 }
+
+#[test]
+fn call_expression() {
+    assert_eq!(
+        parse(
+            r#"sub vcl_recv {
+  set req.http.X = std.tolower(req.http.Host);
+}"#
+        )
+        .unwrap(),
+        Vcl {
+            declarations: vec![Declaration::Subroutine(SubroutineDeclaration {
+                name: Ident::new("vcl_recv", Span(4, 12)),
+                return_type: Type::Void,
+                body: vec![Statement::Set(SetStatement {
+                    target: Variable {
+                        name: "req".to_string(),
+                        properties: vec!["http".to_string(), "X".to_string()],
+                        sub_field: None,
+                        span: Span(21, 32),
+                    },
+                    operator: SetOperator::Set,
+                    value: Expression::Call(CallExpression {
+                        target: Variable {
+                            name: "std".to_string(),
+                            properties: vec!["tolower".to_string()],
+                            sub_field: None,
+                            span: Span(34, 45),
+                        },
+                        arguments: vec![Expression::Variable(Variable {
+                            name: "req".to_string(),
+                            properties: vec!["http".to_string(), "Host".to_string()],
+                            sub_field: None,
+                            span: Span(46, 59),
+                        })],
+                        span: Span(34, 60),
+                    }),
+                    span: Span(17, 61),
+                })],
+                span: Span(13, 63),
+            })],
+        },
+    );
+}
+
+#[test]
+fn call_expression_nested_concat() {
+    assert_eq!(
+        parse(
+            r#"sub vcl_recv {
+  set req.http.Y = "a" regsub(std.tolower(req.http.Host), "x", "") "b";
+  if (std.strlen(req.url) > 10) {}
+}"#
+        )
+        .unwrap(),
+        Vcl {
+            declarations: vec![Declaration::Subroutine(SubroutineDeclaration {
+                name: Ident::new("vcl_recv", Span(4, 12)),
+                return_type: Type::Void,
+                body: vec![
+                    Statement::Set(SetStatement {
+                        target: Variable {
+                            name: "req".to_string(),
+                            properties: vec!["http".to_string(), "Y".to_string()],
+                            sub_field: None,
+                            span: Span(21, 32),
+                        },
+                        operator: SetOperator::Set,
+                        value: Expression::Concat(ConcatExpression {
+                            parts: vec![
+                                Expression::Literal(Literal::String(StringLiteral {
+                                    tokens: vec![StringToken {
+                                        kind: StringKind::Quoted,
+                                        value: "a".to_string(),
+                                        raw: r#""a""#.to_string(),
+                                        span: Span(34, 37),
+                                    }],
+                                    span: Span(34, 38),
+                                })),
+                                Expression::Call(CallExpression {
+                                    target: Variable {
+                                        name: "regsub".to_string(),
+                                        properties: vec![],
+                                        sub_field: None,
+                                        span: Span(38, 44),
+                                    },
+                                    arguments: vec![
+                                        Expression::Call(CallExpression {
+                                            target: Variable {
+                                                name: "std".to_string(),
+                                                properties: vec!["tolower".to_string()],
+                                                sub_field: None,
+                                                span: Span(45, 56),
+                                            },
+                                            arguments: vec![Expression::Variable(Variable {
+                                                name: "req".to_string(),
+                                                properties: vec![
+                                                    "http".to_string(),
+                                                    "Host".to_string()
+                                                ],
+                                                sub_field: None,
+                                                span: Span(57, 70),
+                                            })],
+                                            span: Span(45, 71),
+                                        }),
+                                        Expression::Literal(Literal::String(StringLiteral {
+                                            tokens: vec![StringToken {
+                                                kind: StringKind::Quoted,
+                                                value: "x".to_string(),
+                                                raw: r#""x""#.to_string(),
+                                                span: Span(73, 76),
+                                            }],
+                                            span: Span(73, 76),
+                                        })),
+                                        Expression::Literal(Literal::String(StringLiteral {
+                                            tokens: vec![StringToken {
+                                                kind: StringKind::Empty,
+                                                value: "".to_string(),
+                                                raw: r#""""#.to_string(),
+                                                span: Span(78, 80),
+                                            }],
+                                            span: Span(78, 80),
+                                        })),
+                                    ],
+                                    span: Span(38, 81),
+                                }),
+                                Expression::Literal(Literal::String(StringLiteral {
+                                    tokens: vec![StringToken {
+                                        kind: StringKind::Quoted,
+                                        value: "b".to_string(),
+                                        raw: r#""b""#.to_string(),
+                                        span: Span(82, 85),
+                                    }],
+                                    span: Span(82, 85),
+                                })),
+                            ],
+                            span: Span(34, 85),
+                        }),
+                        span: Span(17, 86),
+                    }),
+                    Statement::If(IfStatement {
+                        condition: Expression::Binary(BinaryExpression {
+                            lhs: Box::new(Expression::Call(CallExpression {
+                                target: Variable {
+                                    name: "std".to_string(),
+                                    properties: vec!["strlen".to_string()],
+                                    sub_field: None,
+                                    span: Span(93, 103),
+                                },
+                                arguments: vec![Expression::Variable(Variable {
+                                    name: "req".to_string(),
+                                    properties: vec!["url".to_string()],
+                                    sub_field: None,
+                                    span: Span(104, 111),
+                                })],
+                                span: Span(93, 112),
+                            })),
+                            operator: BinaryOperator::Gt,
+                            operator_span: Span(113, 114),
+                            rhs: Box::new(Expression::Literal(Literal::Integer(IntegerLiteral {
+                                value: 10,
+                                raw: "10".to_string(),
+                                span: Span(115, 117),
+                            }))),
+                            span: Span(93, 117),
+                        }),
+                        body: vec![],
+                        els: None,
+                        span: Span(89, 122),
+                    }),
+                ],
+                span: Span(13, 123),
+            })],
+        },
+    );
+}
@@ -1,11 +1,9 @@
 use vcls_ast::*;
-use vcls_parser::parse;
+use vcls_parser::{parse, parse_recovering, ErrorCode};
 
 #[test]
 fn table() {
-    assert_eq!(
-        parse(
-            r#"
+    let src = r#"
 table test {
 "foo": "bar", // comment
 {"integer"} /* comment */: 1 ,
@@ -17,25 +15,30 @@ table test {
 "rtime" // : 2m,
 : 1m , "bool" : /* false */true,
 "backend or acl" : backend_or_acl //,
-}"#
-        )
-        .unwrap(),
+}"#;
+    let (vcl, errors) = parse_recovering(src);
+    assert_eq!(
+        vcl,
         Vcl {
             declarations: vec![Declaration::Table(TableDeclaration {
-                name: "test".to_string(),
+                name: Ident::new("test", Span(7, 11)),
                 typ: Type::String,
                 entries: vec![
                     TableEntry {
                         key: StringLiteral {
                             tokens: vec![StringToken {
+                                kind: StringKind::Quoted,
                                 value: "foo".to_string(),
+                                raw: r#""foo""#.to_string(),
                                 span: Span(14, 19),
                             }],
                             span: Span(14, 19),
                         },
                         value: TableValue::Literal(Literal::String(StringLiteral {
                             tokens: vec![StringToken {
+                                kind: StringKind::Quoted,
                                 value: "bar".to_string(),
+                                raw: r#""bar""#.to_string(),
                                 span: Span(21, 26),
                             }],
                             span: Span(21, 26),
@@ -45,13 +48,16 @@ table test {
                     TableEntry {
                         key: StringLiteral {
                             tokens: vec![StringToken {
+                                kind: StringKind::Braces,
                                 value: "integer".to_string(),
+                                raw: r#"{"integer"}"#.to_string(),
                                 span: Span(39, 50),
                             }],
                             span: Span(39, 64),
                         },
                         value: TableValue::Literal(Literal::Integer(IntegerLiteral {
                             value: 1,
+                            raw: "1".to_string(),
                             span: Span(66, 67),
                         })),
                         span: Span(39, 67),
@@ -59,14 +65,18 @@ table test {
                     TableEntry {
                         key: StringLiteral {
                             tokens: vec![StringToken {
+                                kind: StringKind::Empty,
                                 value: "".to_string(),
+                                raw: r#""""#.to_string(),
                                 span: Span(70, 72),
                             }],
                             span: Span(70, 72),
                         },
                         value: TableValue::Literal(Literal::String(StringLiteral {
                             tokens: vec![StringToken {
+                                kind: StringKind::Quoted,
                                 value: "empty // key".to_string(),
+                                raw: r#""empty // key""#.to_string(),
                                 span: Span(74, 88),
                             }],
                             span: Span(74, 88),
@@ -76,14 +86,20 @@ table test {
                     TableEntry {
                         key: StringLiteral {
                             tokens: vec![StringToken {
+                                kind: StringKind::Braces,
                                 value: "/* */\n".to_string(),
+                                raw: "{\"/* */\n\"}".to_string(),
                                 span: Span(90, 100),
                             }],
                             span: Span(90, 100),
                         },
                         value: TableValue::Literal(Literal::String(StringLiteral {
                             tokens: vec![StringToken {
+                                kind: StringKind::Heredoc {
+                                    delimiter: "HEREDOC".to_string(),
+                                },
                                 value: "\n    multiline\n".to_string(),
+                                raw: "{HEREDOC\"\n    multiline\n\"HEREDOC}".to_string(),
                                 span: Span(102, 135),
                             }],
                             span: Span(102, 135),
@@ -93,7 +109,9 @@ table test {
                     TableEntry {
                         key: StringLiteral {
                             tokens: vec![StringToken {
+                                kind: StringKind::Quoted,
                                 value: "rtime".to_string(),
+                                raw: r#""rtime""#.to_string(),
                                 span: Span(137, 144),
                             }],
                             span: Span(137, 154),
@@ -107,7 +125,9 @@ table test {
                     TableEntry {
                         key: StringLiteral {
                             tokens: vec![StringToken {
+                                kind: StringKind::Quoted,
                                 value: "bool".to_string(),
+                                raw: r#""bool""#.to_string(),
                                 span: Span(161, 167),
                             }],
                             span: Span(161, 168),
@@ -121,7 +141,9 @@ table test {
                     TableEntry {
                         key: StringLiteral {
                             tokens: vec![StringToken {
+                                kind: StringKind::Quoted,
                                 value: "backend or acl".to_string(),
+                                raw: r#""backend or acl""#.to_string(),
                                 span: Span(187, 203),
                             }],
                             span: Span(187, 204),
@@ -139,4 +161,72 @@ table test {
             })],
         },
     );
+    // The table is STRING, so every other type is reported.
+    let found: Vec<(ErrorCode, &str)> = errors
+        .iter()
+        .map(|e| (e.code, &src[e.span.0..e.span.1]))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (
+                ErrorCode::InvalidTableValue,
+                r#"{"integer"} /* comment */: 1"#
+            ),
+            (ErrorCode::InvalidTableValue, "\"rtime\" // : 2m,\n: 1m"),
+            (ErrorCode::InvalidTableValue, "\"bool\" : /* false */true"),
+            (
+                ErrorCode::InvalidTableValue,
+                "\"backend or acl\" : backend_or_acl"
+            ),
+        ]
+    );
+}
+
+#[test]
+fn typed_values() {
+    let src = r#"backend origin { .host = "example.com"; }
+acl internal { "192.0.2.0"/24; }
+table backends BACKEND { "a": origin, "b": missing, "c": "origin" }
+table acls ACL { "a": internal, "b": origin }
+table ips IP { "a": "192.0.2.1", "b": "::1", "c": "localhost" }
+table flags BOOL { "a": true, "b": 1 }
+table ttls RTIME { "a": 1m, "b": "foo" }
+table numbers INTEGER { "a": 1, "b": 1.5 }
+table ratios FLOAT { "a": 1, "b": 0.5, "c": "x" }
+table strings { "a": "x", "a": "y", {"a"}: "z" }
+"#;
+    let errors = parse(src).unwrap_err();
+    let found: Vec<(ErrorCode, &str)> = errors
+        .iter()
+        .map(|e| (e.code, &src[e.span.0..e.span.1]))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (ErrorCode::UndefinedReference, r#""b": missing"#),
+            (ErrorCode::InvalidTableValue, r#""c": "origin" "#),
+            (ErrorCode::UndefinedReference, r#""b": origin"#),
+            (ErrorCode::InvalidTableValue, r#""c": "localhost" "#),
+            (ErrorCode::InvalidTableValue, r#""b": 1"#),
+            (ErrorCode::InvalidTableValue, r#""b": "foo" "#),
+            (ErrorCode::InvalidTableValue, r#""b": 1.5"#),
+            (ErrorCode::InvalidTableValue, r#""c": "x" "#),
+            (ErrorCode::DuplicateTableKey, r#""a": "y""#),
+            (ErrorCode::DuplicateTableKey, r#"{"a"}: "z" "#),
+        ]
+    );
+    assert_eq!(
+        errors[1].message,
+        "Table `backends` has BACKEND values, found STRING"
+    );
+    assert_eq!(
+        errors[8].related[0].span.0,
+        src.find(r#""a": "x""#).unwrap()
+    );
+}
+
+#[test]
+fn references_with_include() {
+    assert!(parse(r#"include "backends"; table t BACKEND { "a": origin }"#).is_ok());
 }